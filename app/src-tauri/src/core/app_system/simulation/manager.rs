@@ -5,6 +5,13 @@ use crate::core::sciences::maths::vector2::Vector2;
 use crate::core::apps::bouncing_balls::main::BouncingBallSimulation;
 use crate::core::apps::fluid::main::Fluid;
 use crate::core::apps::rigibody::main::RigidSimulation;
+use crate::core::apps::fluid_rigid::main::FluidRigidSimulation;
+use crate::core::apps::smoke::main::Smoke;
+use crate::core::apps::flip::main::FlipFluid;
+use crate::core::apps::sand::main::Sand;
+use crate::core::apps::nbody::main::NBody;
+use crate::core::apps::electrostatics::main::Electrostatics;
+use crate::core::apps::ideal_gas::main::IdealGas;
 
 use super::renderer::Renderer;
 use super::template::SimulationTemplate;
@@ -171,6 +178,62 @@ pub async fn select_simulation_template(window: tauri::Window, simulation_manage
             println!("Rigid body simulation selected");
             Box::new(RigidSimulation::new(renderer.size, None))
         },
+        3 => {
+            println!("Fluid and rigid body simulation selected");
+            let gradient = match colorgrad::CustomGradient::new().html_colors(&["#0077ff", "#24ff6f", "ffff20", "ff3131"]).domain(&[0.0, 0.5, 0.7, 1.0]).build() {
+                Ok(gradient) => gradient,
+                Err(e) => return Err(e.to_string())
+            };
+            Box::new(FluidRigidSimulation::new(gradient))
+        },
+        4 => {
+            println!("Smoke simulation selected");
+            let gradient = match colorgrad::CustomGradient::new().html_colors(&["#10101a", "#8a8aa0", "#ffffff"]).domain(&[0.0, 0.5, 1.0]).build() {
+                Ok(gradient) => gradient,
+                Err(e) => return Err(e.to_string())
+            };
+            Box::new(Smoke::new(gradient))
+        },
+        5 => {
+            println!("FLIP fluid simulation selected");
+            let gradient = match colorgrad::CustomGradient::new().html_colors(&["#0077ff", "#24ff6f", "ffff20", "ff3131"]).domain(&[0.0, 0.5, 0.7, 1.0]).build() {
+                Ok(gradient) => gradient,
+                Err(e) => return Err(e.to_string())
+            };
+            Box::new(FlipFluid::new(gradient))
+        },
+        6 => {
+            println!("Sand simulation selected");
+            let gradient = match colorgrad::CustomGradient::new().html_colors(&["#a67c3d", "#c2a15a", "#e0c27a"]).domain(&[0.0, 0.5, 1.0]).build() {
+                Ok(gradient) => gradient,
+                Err(e) => return Err(e.to_string())
+            };
+            Box::new(Sand::new(gradient))
+        },
+        7 => {
+            println!("N-body simulation selected");
+            let gradient = match colorgrad::CustomGradient::new().html_colors(&["#ffd9a0", "#ffffff", "#9ab8ff"]).domain(&[0.0, 0.5, 1.0]).build() {
+                Ok(gradient) => gradient,
+                Err(e) => return Err(e.to_string())
+            };
+            Box::new(NBody::new(gradient))
+        },
+        8 => {
+            println!("Electrostatics simulation selected");
+            let gradient = match colorgrad::CustomGradient::new().html_colors(&["#2f6bff", "#101018", "#ff3b3b"]).domain(&[0.0, 0.5, 1.0]).build() {
+                Ok(gradient) => gradient,
+                Err(e) => return Err(e.to_string())
+            };
+            Box::new(Electrostatics::new(gradient))
+        },
+        9 => {
+            println!("Ideal gas simulation selected");
+            let gradient = match colorgrad::CustomGradient::new().html_colors(&["#0077ff", "#24ff6f", "ffff20", "ff3131"]).domain(&[0.0, 0.5, 0.7, 1.0]).build() {
+                Ok(gradient) => gradient,
+                Err(e) => return Err(e.to_string())
+            };
+            Box::new(IdealGas::new(gradient))
+        },
 
         _ => return Err("Invalid simulation template ID".to_string())
     };
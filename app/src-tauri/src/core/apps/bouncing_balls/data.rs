@@ -4,6 +4,8 @@ use colorgrad::Color;
 
 use crate::core::app_system::simulation::renderer::DataToSend;
 use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::maths::integrators::Integrator;
+use crate::core::sciences::physics::force_fields::ForceField;
 
 #[derive(Serialize, Clone)]
 pub struct Ball {
@@ -11,17 +13,23 @@ pub struct Ball {
     pub velocity: Vector2,
     pub radius: f32,
     pub mass: f32,
+    /// Coefficient of restitution, 1 for an elastic ball and 0 for a perfectly inelastic one.
+    pub restitution: f32,
     pub color: String, // store in hex format and not in colorgrad::Color to allow serialization
+    /// Whether the color was chosen by the user, instead of following the velocity.
+    pub custom_color: bool,
 }
 
 impl Ball {
-    pub fn new(position: Vector2, velocity: Vector2, radius: f32, mass: f32, color: Color) -> Self {
+    pub fn new(position: Vector2, velocity: Vector2, radius: f32, mass: f32, restitution: f32, color: Color) -> Self {
         Ball {
             position,
             velocity,
             radius,
             mass,
-            color: color.to_hex_string()
+            restitution,
+            color: color.to_hex_string(),
+            custom_color: false
         }
     }
 }
@@ -29,6 +37,12 @@ impl Ball {
 #[derive(Serialize, Clone)]
 pub struct RendererData {
     pub balls: Vec<Ball>,
+    pub force_fields: Vec<ForceField>,
+    /// Total momentum of the balls.
+    pub momentum: Vector2,
+    pub kinetic_energy: f32,
+    /// Gravitational potential energy of the balls, with the floor as reference.
+    pub potential_energy: f32,
 }
 
 impl DataToSend for RendererData {}
@@ -36,4 +50,44 @@ impl DataToSend for RendererData {}
 #[derive(Deserialize, Clone)]
 pub struct StarterData {
     pub positions: Vec<Vector2>
+}
+
+/// Ball held with the pointer, pulled towards the pointer position.
+#[derive(Clone, Copy, Debug)]
+pub struct Grab {
+    pub index: usize,
+    pub target: Vector2,
+}
+
+/// Gravity and properties of the spawned balls.
+#[derive(Deserialize)]
+pub struct EventSettings {
+    pub gravity: f32,
+    pub integrator: Integrator,
+    pub radius: f32,
+    pub mass: f32,
+    pub restitution: f32,
+}
+
+#[derive(Deserialize)]
+pub struct EventSpawnBall {
+    pub position: Vector2,
+    #[serde(default = "Vector2::zero")]
+    pub velocity: Vector2,
+}
+
+/// Position of the pointer grabbing or moving a ball.
+#[derive(Deserialize)]
+pub struct EventPointer {
+    pub position: Vector2,
+}
+
+#[derive(Deserialize)]
+pub struct EventEditBall {
+    pub index: usize,
+    pub radius: f32,
+    pub mass: f32,
+    pub restitution: f32,
+    /// Color in hex format, `None` to color the ball by its velocity again.
+    pub color: Option<String>,
 }
\ No newline at end of file
@@ -1,6 +1,11 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
 use colorgrad::{Color, Gradient};
 use super::data::*;
 use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::maths::integrators::Integrator;
+use crate::core::sciences::physics::force_fields::ForceField;
 
 /// Represents a bouncing ball simulation.
 /// 
@@ -13,11 +18,23 @@ pub struct BouncingBallSimulation {
     pub renderer_size: Vector2,
     pub balls: Vec<Ball>,
     pub velocity_gradient: Gradient,
+    pub gravity: f32,
+    /// Scheme moving the balls between the collisions.
+    pub integrator: Integrator,
+    /// External force fields, added to the gravity.
+    pub force_fields: Vec<ForceField>,
     pub default_position: Vector2,
     pub default_velocity: Vector2,
     pub default_radius: f32,
     pub default_mass: f32,
-    pub default_color: Color
+    pub default_restitution: f32,
+    pub default_color: Color,
+    /// Number of passes over the contacts per step, needed for the balls resting on each other.
+    pub collision_iterations: usize,
+    /// Ball held with the pointer, if any.
+    pub grab: Option<Grab>,
+    /// Rate at which a grabbed ball closes the gap to the pointer, in 1/s. Also sets the speed of a throw.
+    pub grab_stiffness: f32
 }
 
 impl BouncingBallSimulation {
@@ -41,19 +58,26 @@ impl BouncingBallSimulation {
         BouncingBallSimulation {
             renderer_size,
             balls: Vec::new(),
+            gravity: 750.0,
+            integrator: Integrator::default(),
+            force_fields: Vec::new(),
             default_position: if let Some(position) = default_position {position} else {Vector2::new(renderer_size.x / 2.0, renderer_size.y / 2.0)},
             default_velocity: if let Some(velocity) = default_velocity {velocity} else {Vector2::zero()},
             default_radius: radius,
             default_mass: radius*5.0,
+            default_restitution: 0.8,
             default_color: if let Some(color) = default_color {color} else {velocity_gradient.at(0.0)},
             velocity_gradient,
+            collision_iterations: 10,
+            grab: None,
+            grab_stiffness: 30.0,
         }
     }
 
     /// Creates a new ball with the default position, velocity, radius, mass, and color, and adds it to the simulation.
     pub fn add_ball(&mut self) {
         let ball = Ball::new(
-            self.default_position, self.default_velocity, self.default_radius, self.default_mass, self.default_color.clone()
+            self.default_position, self.default_velocity, self.default_radius, self.default_mass, self.default_restitution, self.default_color.clone()
         );
 
         self.push_ball(ball);
@@ -76,4 +100,176 @@ impl BouncingBallSimulation {
     pub fn append_balls(&mut self, balls: &mut Vec<Ball>) {
         self.balls.append(balls);
     }
+
+    /// Removes the ball at `index`, keeping the grab on the right ball.
+    pub fn remove_ball(&mut self, index: usize) -> Result<(), String> {
+        if index >= self.balls.len() {
+            return Err(format!("No ball at index {}", index));
+        }
+
+        self.balls.remove(index);
+        self.grab = match self.grab {
+            Some(grab) if grab.index == index => None,
+            Some(grab) if grab.index > index => Some(Grab { index: grab.index - 1, ..grab }),
+            grab => grab
+        };
+
+        Ok(())
+    }
+
+    /// Returns the index of the ball under `position`, the one whose center is the closest if they overlap.
+    pub fn find_ball_at(&self, position: Vector2) -> Option<usize> {
+        self.balls.iter().enumerate()
+            .filter(|(_, ball)| ball.position.distance_to_squared(position) <= ball.radius * ball.radius)
+            .min_by(|(_, a), (_, b)| a.position.distance_to_squared(position).partial_cmp(&b.position.distance_to_squared(position)).unwrap_or(Ordering::Equal))
+            .map(|(index, _)| index)
+    }
+
+    /// Pulls the grabbed ball towards the pointer, ignoring gravity and the force fields.
+    ///
+    /// The ball keeps the velocity of the pull when it is released, which throws it.
+    pub fn move_grabbed_ball(&mut self, dt: f32) {
+        if let Some(grab) = self.grab {
+            let ball = &mut self.balls[grab.index];
+            // Never overshoot the pointer, even for large timesteps
+            let rate = self.grab_stiffness.min(1.0 / dt);
+            ball.velocity = (grab.target - ball.position) * rate;
+            ball.position += ball.velocity * dt;
+        }
+    }
+
+    /// Finds the pairs of balls separated by a gap smaller than `margin`, the broad phase of the collision detection.
+    ///
+    /// The balls are sorted in a uniform grid whose cells are as large as the largest ball plus the margin,
+    /// so that a ball can only be close to the balls of its cell and of the 8 cells around it.
+    ///
+    /// # Returns
+    ///
+    /// The pairs `(i, j)` of close balls, with `i < j`.
+    pub fn find_close_pairs(&self, margin: f32) -> Vec<(usize, usize)> {
+        let cell_size = 2.0 * self.balls.iter().map(|ball| ball.radius).fold(f32::EPSILON, f32::max) + margin;
+        let cell_of = |position: Vector2| ((position.x / cell_size).floor() as i32, (position.y / cell_size).floor() as i32);
+
+        let mut grid: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (i, ball) in self.balls.iter().enumerate() {
+            grid.entry(cell_of(ball.position)).or_default().push(i);
+        }
+
+        let mut pairs = Vec::new();
+        for (i, ball) in self.balls.iter().enumerate() {
+            let (x, y) = cell_of(ball.position);
+
+            for (dx, dy) in (-1..=1).flat_map(|dx| (-1..=1).map(move |dy| (dx, dy))) {
+                if let Some(cell) = grid.get(&(x + dx, y + dy)) {
+                    for &j in cell.iter().filter(|j| **j > i) {
+                        let other = &self.balls[j];
+                        let min_distance = ball.radius + other.radius + margin;
+                        if ball.position.distance_to_squared(other.position) < min_distance * min_distance {
+                            pairs.push((i, j));
+                        }
+                    }
+                }
+            }
+        }
+
+        pairs
+    }
+
+    /// Resolves the collisions between the balls and with the renderer bounds.
+    ///
+    /// The contacts slower than what gravity adds in two steps do not bounce, so that the balls can rest on each other.
+    /// The close pairs are searched once, the balls moving much less than their radius during the iterations.
+    pub fn resolve_collisions(&mut self, dt: f32) {
+        let resting_speed = 2.0 * self.gravity.abs() * dt;
+        let margin = self.balls.iter().map(|ball| ball.radius).fold(0.0, f32::max);
+        let pairs = self.find_close_pairs(margin);
+
+        for _ in 0..self.collision_iterations {
+            self.resolve_ball_collisions(&pairs, resting_speed);
+            self.resolve_wall_collisions(resting_speed);
+        }
+    }
+
+    /// Separates the overlapping balls and exchanges their momentum.
+    ///
+    /// The impulse along the line of centers is weighted by the masses, so that the momentum is conserved,
+    /// and uses the smallest restitution of the two balls: a collision is elastic only between elastic balls.
+    fn resolve_ball_collisions(&mut self, pairs: &[(usize, usize)], resting_speed: f32) {
+        for &(i, j) in pairs {
+            let (a, b) = (&self.balls[i], &self.balls[j]);
+            let offset = b.position - a.position;
+            let distance = offset.magnitude();
+            if distance >= a.radius + b.radius {
+                continue;
+            }
+
+            let normal = if distance > f32::EPSILON { offset / distance } else { Vector2::right() };
+
+            let inverse_mass_a = if a.mass > 0.0 { 1.0 / a.mass } else { 0.0 };
+            let inverse_mass_b = if b.mass > 0.0 { 1.0 / b.mass } else { 0.0 };
+            let inverse_mass_sum = inverse_mass_a + inverse_mass_b;
+            if inverse_mass_sum <= 0.0 {
+                continue;
+            }
+
+            // Push the balls apart, the lighter one moving more
+            let overlap = a.radius + b.radius - distance;
+            let correction = normal * (overlap / inverse_mass_sum);
+            let approach_speed = (b.velocity - a.velocity) * normal;
+            let restitution = if -approach_speed < resting_speed { 0.0 } else { a.restitution.min(b.restitution) };
+
+            self.balls[i].position -= correction * inverse_mass_a;
+            self.balls[j].position += correction * inverse_mass_b;
+
+            // Only the balls moving towards each other bounce
+            if approach_speed >= 0.0 {
+                continue;
+            }
+
+            let impulse = normal * (-(1.0 + restitution) * approach_speed / inverse_mass_sum);
+            self.balls[i].velocity -= impulse * inverse_mass_a;
+            self.balls[j].velocity += impulse * inverse_mass_b;
+        }
+    }
+
+    /// Keeps the balls inside the renderer bounds, bouncing the ones moving outwards.
+    fn resolve_wall_collisions(&mut self, resting_speed: f32) {
+        let bounce = |velocity: f32, restitution: f32| if velocity.abs() < resting_speed { 0.0 } else { -velocity * restitution };
+
+        for ball in &mut self.balls {
+            if ball.position.x - ball.radius < 0.0 {
+                ball.position.x = ball.radius;
+                if ball.velocity.x < 0.0 { ball.velocity.x = bounce(ball.velocity.x, ball.restitution); }
+            }
+            if ball.position.x + ball.radius > self.renderer_size.x {
+                ball.position.x = self.renderer_size.x - ball.radius;
+                if ball.velocity.x > 0.0 { ball.velocity.x = bounce(ball.velocity.x, ball.restitution); }
+            }
+            if ball.position.y - ball.radius < 0.0 {
+                ball.position.y = ball.radius;
+                if ball.velocity.y < 0.0 { ball.velocity.y = bounce(ball.velocity.y, ball.restitution); }
+            }
+            if ball.position.y + ball.radius > self.renderer_size.y {
+                ball.position.y = self.renderer_size.y - ball.radius;
+                if ball.velocity.y > 0.0 { ball.velocity.y = bounce(ball.velocity.y, ball.restitution); }
+            }
+        }
+    }
+
+    /// Total momentum of the balls.
+    pub fn calculate_momentum(&self) -> Vector2 {
+        self.balls.iter().fold(Vector2::zero(), |momentum, ball| momentum + ball.velocity * ball.mass)
+    }
+
+    /// Total kinetic energy of the balls.
+    pub fn calculate_kinetic_energy(&self) -> f32 {
+        self.balls.iter().map(|ball| 0.5 * ball.mass * ball.velocity.magnitude_squared()).sum()
+    }
+
+    /// Total gravitational potential energy of the balls, taking the floor as reference.
+    ///
+    /// The force fields are not included, most of them not deriving from a potential.
+    pub fn calculate_potential_energy(&self) -> f32 {
+        self.balls.iter().map(|ball| ball.mass * self.gravity * (self.renderer_size.y - ball.position.y)).sum()
+    }
 }
\ No newline at end of file
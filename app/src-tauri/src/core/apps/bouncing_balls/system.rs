@@ -1,6 +1,9 @@
+use colorgrad::Color;
+
 use crate::core::app_system::simulation::template::SimulationTemplate;
 use crate::core::app_system::simulation::renderer::DataToSend;
 use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::physics::force_fields::{handle_force_field_event, total_acceleration};
 
 use super::main::BouncingBallSimulation;
 use super::data::*;
@@ -20,7 +23,7 @@ impl SimulationTemplate for BouncingBallSimulation {
 
         for position in starter_data.positions {
             let ball = Ball::new(
-                position, self.default_velocity, self.default_radius, self.default_mass, self.default_color.clone()
+                position, self.default_velocity, self.default_radius, self.default_mass, self.default_restitution, self.default_color.clone()
             );
             self.push_ball(ball);
         }
@@ -29,28 +32,24 @@ impl SimulationTemplate for BouncingBallSimulation {
     }
 
     fn next_step(&mut self, dt: f32) -> Result<(), String> {
-        for ball in &mut self.balls {
-            // Apply gravity
-            ball.velocity += Vector2::down() * 9.81 * ball.mass * dt;
-            // Update position
-            ball.position += ball.velocity * dt;
-            // Check for collision with the renderer bounds
-            if ball.position.x - ball.radius < 0.0 {
-                ball.position.x = ball.radius;
-                ball.velocity.x *= -0.8;
-            }
-            if ball.position.x + ball.radius > self.renderer_size.x {
-                ball.position.x = self.renderer_size.x - ball.radius;
-                ball.velocity.x *= -0.8;
-            }
-            if ball.position.y - ball.radius < 0.0 {
-                ball.position.y = ball.radius;
-                ball.velocity.y *= -0.8;
-            }
-            if ball.position.y + ball.radius > self.renderer_size.y {
-                ball.position.y = self.renderer_size.y - ball.radius;
-                ball.velocity.y *= -0.8;
+        let grabbed_index = self.grab.map(|grab| grab.index);
+        let gravity = Vector2::down() * self.gravity;
+        let force_fields = &self.force_fields;
+        for (index, ball) in self.balls.iter_mut().enumerate() {
+            if Some(index) == grabbed_index {
+                continue;
             }
+
+            // Apply gravity and the force fields
+            (ball.position, ball.velocity) = self.integrator.step(ball.position, ball.velocity, dt, |position, velocity| {
+                gravity + total_acceleration(force_fields, position, velocity)
+            });
+        }
+
+        self.move_grabbed_ball(dt);
+        self.resolve_collisions(dt);
+
+        for ball in self.balls.iter_mut().filter(|ball| !ball.custom_color) {
             // Update color in function of the velocity
             let normalized_velocity = ball.velocity.magnitude() / 1000.0;
             ball.color = self.velocity_gradient.at(normalized_velocity as f64).to_hex_string();
@@ -61,7 +60,11 @@ impl SimulationTemplate for BouncingBallSimulation {
 
     fn get_data_to_render(&self) -> Result<Box<dyn DataToSend>, String> {
         let data_to_render = RendererData {
-            balls: self.balls.clone()
+            balls: self.balls.clone(),
+            force_fields: self.force_fields.clone(),
+            momentum: self.calculate_momentum(),
+            kinetic_energy: self.calculate_kinetic_energy(),
+            potential_energy: self.calculate_potential_energy()
         };
         
         Ok(Box::new(data_to_render))
@@ -75,10 +78,126 @@ impl SimulationTemplate for BouncingBallSimulation {
                     self.default_velocity,
                     self.default_radius,
                     self.default_mass,
+                    self.default_restitution,
                     self.default_color.clone()
                 );
                 self.push_ball(ball);
             },
+            "spawn_ball" => {
+                match data {
+                    Some(data) => {
+                        let spawn: EventSpawnBall = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        let ball = Ball::new(
+                            spawn.position, spawn.velocity, self.default_radius, self.default_mass, self.default_restitution, self.default_color.clone()
+                        );
+                        self.push_ball(ball);
+                    },
+                    None => return Err("No data provided for the 'spawn_ball' event".to_string())
+                }
+            },
+            "grab_ball" => {
+                match data {
+                    Some(data) => {
+                        let pointer: EventPointer = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        // Grabbing the empty space does nothing
+                        self.grab = self.find_ball_at(pointer.position).map(|index| Grab { index, target: pointer.position });
+                    },
+                    None => return Err("No data provided for the 'grab_ball' event".to_string())
+                }
+            },
+            "move_grab" => {
+                match data {
+                    Some(data) => {
+                        let pointer: EventPointer = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        if let Some(grab) = &mut self.grab {
+                            grab.target = pointer.position;
+                        }
+                    },
+                    None => return Err("No data provided for the 'move_grab' event".to_string())
+                }
+            },
+            "release_ball" => {
+                self.grab = None;
+            },
+            "remove_ball" => {
+                match data {
+                    Some(data) => {
+                        let index: usize = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.remove_ball(index)?;
+                    },
+                    None => return Err("No data provided for the 'remove_ball' event".to_string())
+                }
+            },
+            "clear_balls" => {
+                self.balls.clear();
+                self.grab = None;
+            },
+            "edit_ball" => {
+                match data {
+                    Some(data) => {
+                        let edit: EventEditBall = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        let ball = match self.balls.get_mut(edit.index) {
+                            Some(ball) => ball,
+                            None => return Err(format!("No ball at index {}", edit.index))
+                        };
+
+                        ball.radius = edit.radius.max(1.0);
+                        ball.mass = edit.mass.max(f32::EPSILON);
+                        ball.restitution = edit.restitution.clamp(0.0, 1.0);
+                        match edit.color {
+                            Some(color) => {
+                                ball.color = match Color::from_html(&color) {
+                                    Ok(color) => color.to_hex_string(),
+                                    Err(e) => return Err(e.to_string())
+                                };
+                                ball.custom_color = true;
+                            },
+                            None => ball.custom_color = false
+                        }
+                    },
+                    None => return Err("No data provided for the 'edit_ball' event".to_string())
+                }
+            },
+            "set_settings" => {
+                match data {
+                    Some(data) => {
+                        let settings: EventSettings = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.gravity = settings.gravity;
+                        self.integrator = settings.integrator;
+                        self.default_radius = settings.radius.max(1.0);
+                        self.default_mass = settings.mass.max(f32::EPSILON);
+                        self.default_restitution = settings.restitution.clamp(0.0, 1.0);
+                    },
+                    None => return Err("No data provided for the 'set_settings' event".to_string())
+                }
+            },
+            "add_force_field" | "remove_force_field" | "clear_force_fields" => {
+                handle_force_field_event(&mut self.force_fields, &event, data)?;
+            },
             _ => return Err("Unknown event".to_string())
         }
 
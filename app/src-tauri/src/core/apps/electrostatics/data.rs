@@ -0,0 +1,74 @@
+use serde::{Serialize, Deserialize};
+
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::physics::electromagnetism::electrostatics::FieldGrid;
+use crate::core::sciences::physics::force_fields::ForceField;
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Charge {
+    pub position: Vector2,
+    pub velocity: Vector2,
+    pub charge: f32,
+    pub mass: f32,
+    /// A fixed charge creates a field but is not moved by it.
+    pub fixed: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RendererData {
+    pub charges: Vec<Charge>,
+    pub charge_radius: f32,
+    /// Potential and field at the cell centers, empty unless the potential is shown.
+    pub field_grid: FieldGrid,
+    /// The potential as an RGBA image of `field_grid.width` × `field_grid.height` pixels, one pixel per cell.
+    pub pixels: Vec<u8>,
+    /// Field lines as polylines, empty unless they are shown.
+    pub field_lines: Vec<Vec<Vector2>>,
+    pub force_fields: Vec<ForceField>,
+}
+
+impl DataToSend for RendererData {}
+
+#[derive(Deserialize, Clone)]
+pub struct StarterData {
+    pub charges: Vec<EventAddCharge>,
+}
+
+#[derive(Deserialize)]
+pub struct EventSettings {
+    pub coulomb_constant: f32,
+    pub softening: f32,
+    pub external_field: Vector2,
+    /// Rate at which the velocity of the mobile charges is damped, letting them settle.
+    pub damping: f32,
+    pub restitution: f32,
+    /// Rate at which the sliding of touching charges on each other is damped.
+    pub friction: f32,
+    pub show_potential: bool,
+    pub show_field_lines: bool,
+    pub grid_cell_size: f32,
+    pub lines_per_charge: usize,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct EventAddCharge {
+    pub position: Vector2,
+    pub charge: f32,
+    #[serde(default)]
+    pub fixed: bool,
+    #[serde(default = "Vector2::zero")]
+    pub velocity: Vector2,
+    #[serde(default = "default_mass")]
+    pub mass: f32,
+}
+
+fn default_mass() -> f32 {
+    1.0
+}
+
+/// Position of the pointer grabbing or moving a charge.
+#[derive(Deserialize)]
+pub struct EventPointer {
+    pub position: Vector2,
+}
@@ -0,0 +1,315 @@
+use std::cmp::Ordering;
+
+use colorgrad::Gradient;
+
+use crate::core::sciences::maths::Vector2;
+use crate::core::sciences::physics::electromagnetism::electrostatics::{ElectrostaticField, FieldGrid, PointCharge};
+use crate::core::sciences::physics::force_fields::{ForceField, total_acceleration};
+
+use super::data::{Charge, EventAddCharge};
+
+/// Distance a charge must move from where the render data was computed before it is computed again.
+const RENDER_TOLERANCE: f32 = 0.5;
+
+/// Charged particles interacting through the Coulomb force.
+///
+/// The fixed charges create a field without moving, and the mobile ones are moved by the field of all
+/// the others with the kick-drift-kick leapfrog, bouncing on each other and on the walls. The potential
+/// and the field lines are computed for the renderer from the same softened field, only when a charge
+/// has moved or the settings have changed since the last time.
+pub struct Electrostatics {
+    // CHARGE PROPERTIES
+    pub charges: Vec<Charge>,
+    /// Accelerations of the charges at their current positions, kept from a step to the next by the leapfrog.
+    pub accelerations: Vec<Vector2>,
+    pub max_charges: usize,
+    pub charge_radius: f32,
+    // FIELD PROPERTIES
+    pub field: ElectrostaticField,
+    /// External force fields, added to the electric force on the mobile charges.
+    pub force_fields: Vec<ForceField>,
+    pub damping: f32,
+    pub restitution: f32,
+    /// Rate at which the sliding of touching charges on each other is damped. Without it, a pair
+    /// of opposite charges keeps orbiting in contact, the contacts giving back the spin taken by the damping.
+    pub friction: f32,
+    // TIMESTEP PROPERTIES
+    /// Fraction of the time needed to cross the softening length from rest at the largest acceleration.
+    pub timestep_accuracy: f32,
+    pub max_substeps: usize,
+    pub substeps: usize,
+    // BOUNDARY PROPERTIES
+    pub box_size: Vector2,
+    // INTERACTION PROPERTIES
+    /// Index of the charge held with the pointer, which stays where the pointer is.
+    pub held_charge: Option<usize>,
+    // RENDER PROPERTIES
+    pub show_potential: bool,
+    pub show_field_lines: bool,
+    pub grid_cell_size: f32,
+    pub lines_per_charge: usize,
+    /// Whether the render data must be computed again, after a change of the charges or the settings.
+    pub render_outdated: bool,
+    /// Positions of the charges when the render data was last computed.
+    pub rendered_positions: Vec<Vector2>,
+    pub field_grid: FieldGrid,
+    /// The potential of `field_grid` as RGBA pixels.
+    pub pixels: Vec<u8>,
+    pub field_lines: Vec<Vec<Vector2>>,
+    /// Diverging gradient of the potential, from negative to positive.
+    pub potential_gradient: Gradient
+}
+
+impl Electrostatics {
+    pub fn new(potential_gradient: Gradient) -> Self {
+        Electrostatics {
+            // CHARGE PROPERTIES
+            charges: Vec::new(),
+            accelerations: Vec::new(),
+            max_charges: 300,
+            charge_radius: 8.0,
+            // FIELD PROPERTIES
+            field: ElectrostaticField {
+                coulomb_constant: 1e6,
+                softening: 5.0,
+                external_field: Vector2::zero(),
+            },
+            force_fields: Vec::new(),
+            damping: 0.5,
+            restitution: 0.5,
+            friction: 10.0,
+            // TIMESTEP PROPERTIES
+            timestep_accuracy: 0.2,
+            max_substeps: 20,
+            substeps: 1,
+            // BOUNDARY PROPERTIES
+            box_size: Vector2::new(800.0, 600.0),
+            // INTERACTION PROPERTIES
+            held_charge: None,
+            // RENDER PROPERTIES
+            show_potential: true,
+            show_field_lines: true,
+            grid_cell_size: 8.0,
+            lines_per_charge: 16,
+            render_outdated: true,
+            rendered_positions: Vec::new(),
+            field_grid: FieldGrid::default(),
+            pixels: Vec::new(),
+            field_lines: Vec::new(),
+            potential_gradient
+        }
+    }
+
+    pub fn add_charge(&mut self, charge: &EventAddCharge) -> Result<(), String> {
+        if self.charges.len() >= self.max_charges {
+            return Err(format!("Too many charges (maximum {})", self.max_charges));
+        }
+
+        self.charges.push(Charge {
+            position: charge.position,
+            velocity: if charge.fixed { Vector2::zero() } else { charge.velocity },
+            charge: charge.charge,
+            mass: charge.mass.max(f32::EPSILON),
+            fixed: charge.fixed,
+        });
+        self.accelerations.clear();
+        self.render_outdated = true;
+
+        Ok(())
+    }
+
+    /// Removes the charge at `index`, keeping the hold on the right charge.
+    pub fn remove_charge(&mut self, index: usize) -> Result<(), String> {
+        if index >= self.charges.len() {
+            return Err(format!("No charge at index {}", index));
+        }
+
+        self.charges.remove(index);
+        self.accelerations.clear();
+        self.render_outdated = true;
+        self.held_charge = match self.held_charge {
+            Some(held) if held == index => None,
+            Some(held) if held > index => Some(held - 1),
+            held => held
+        };
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.charges.clear();
+        self.accelerations.clear();
+        self.held_charge = None;
+        self.render_outdated = true;
+    }
+
+    /// Returns the index of the charge under `position`, the closest one if they overlap.
+    pub fn find_charge_at(&self, position: Vector2) -> Option<usize> {
+        self.charges.iter().enumerate()
+            .filter(|(_, charge)| charge.position.distance_to(position) <= self.charge_radius)
+            .min_by(|(_, a), (_, b)| a.position.distance_to_squared(position).partial_cmp(&b.position.distance_to_squared(position)).unwrap_or(Ordering::Equal))
+            .map(|(index, _)| index)
+    }
+
+    /// Whether the charge `i` is moved by the field: neither fixed nor held.
+    fn is_mobile(&self, i: usize) -> bool {
+        !self.charges[i].fixed && self.held_charge != Some(i)
+    }
+
+    pub fn sources(&self) -> Vec<PointCharge> {
+        self.charges.iter().map(|charge| PointCharge { position: charge.position, charge: charge.charge }).collect()
+    }
+
+    /// Computes the accelerations of the mobile charges, the fixed and held ones having none.
+    pub fn compute_accelerations(&mut self) {
+        let sources = self.sources();
+
+        self.accelerations = (0..self.charges.len()).map(|i| {
+            if !self.is_mobile(i) {
+                return Vector2::zero();
+            }
+
+            let charge = &self.charges[i];
+            self.field.field(&sources, charge.position) * (charge.charge / charge.mass)
+                + total_acceleration(&self.force_fields, charge.position, charge.velocity)
+                - charge.velocity * self.damping
+        }).collect();
+    }
+
+    /// Splits `dt` so that no charge crosses more than a fraction of the softening length from rest during a substep.
+    pub fn plan_substeps(&mut self, dt: f32) -> f32 {
+        let max_acceleration = self.accelerations.iter().map(|acceleration| acceleration.magnitude()).fold(0.0, f32::max);
+        let stable_dt = if max_acceleration > 0.0 {
+            self.timestep_accuracy * (self.field.softening.max(f32::EPSILON) / max_acceleration).sqrt()
+        } else {
+            f32::INFINITY
+        };
+
+        self.substeps = ((dt / stable_dt).ceil() as usize).clamp(1, self.max_substeps);
+        dt / self.substeps as f32
+    }
+
+    /// Advances the mobile charges by `dt` with the kick-drift-kick leapfrog, then resolves the contacts.
+    pub fn update(&mut self, dt: f32) {
+        if self.accelerations.len() != self.charges.len() {
+            self.compute_accelerations();
+        }
+
+        let substep_dt = self.plan_substeps(dt);
+        for _ in 0..self.substeps {
+            for i in 0..self.charges.len() {
+                self.charges[i].velocity += self.accelerations[i] * (0.5 * substep_dt);
+                let velocity = self.charges[i].velocity;
+                self.charges[i].position += velocity * substep_dt;
+            }
+
+            self.resolve_collisions(substep_dt);
+            self.compute_accelerations();
+
+            for i in 0..self.charges.len() {
+                self.charges[i].velocity += self.accelerations[i] * (0.5 * substep_dt);
+            }
+        }
+    }
+
+    /// Separates the overlapping charges and keeps the mobile ones inside the box, removing the approaching
+    /// velocity but the part given back by the restitution, and damping the sliding during `dt`.
+    fn resolve_collisions(&mut self, dt: f32) {
+        let min_distance = 2.0 * self.charge_radius;
+        let sliding_kept = (-self.friction * dt).exp();
+
+        for i in 0..self.charges.len() {
+            for j in i + 1..self.charges.len() {
+                let inverse_mass_i = if self.is_mobile(i) { 1.0 / self.charges[i].mass } else { 0.0 };
+                let inverse_mass_j = if self.is_mobile(j) { 1.0 / self.charges[j].mass } else { 0.0 };
+                let inverse_mass_sum = inverse_mass_i + inverse_mass_j;
+                if inverse_mass_sum <= 0.0 {
+                    continue;
+                }
+
+                let offset = self.charges[j].position - self.charges[i].position;
+                let distance = offset.magnitude();
+                if distance >= min_distance {
+                    continue;
+                }
+
+                let normal = if distance > f32::EPSILON { offset / distance } else { Vector2::right() };
+                let correction = normal * ((min_distance - distance) / inverse_mass_sum);
+                self.charges[i].position -= correction * inverse_mass_i;
+                self.charges[j].position += correction * inverse_mass_j;
+
+                let relative_velocity = self.charges[j].velocity - self.charges[i].velocity;
+                let approach_speed = relative_velocity * normal;
+                let sliding_speed = relative_velocity * normal.perpendicular();
+                let normal_change = if approach_speed < 0.0 { -(1.0 + self.restitution) * approach_speed } else { 0.0 };
+                let impulse = (normal * normal_change - normal.perpendicular() * (sliding_speed * (1.0 - sliding_kept))) / inverse_mass_sum;
+                self.charges[i].velocity -= impulse * inverse_mass_i;
+                self.charges[j].velocity += impulse * inverse_mass_j;
+            }
+        }
+
+        for i in 0..self.charges.len() {
+            if !self.is_mobile(i) {
+                continue;
+            }
+
+            let (radius, restitution, box_size) = (self.charge_radius, self.restitution, self.box_size);
+            let charge = &mut self.charges[i];
+            if charge.position.x < radius || charge.position.x > box_size.x - radius {
+                charge.position.x = charge.position.x.clamp(radius, (box_size.x - radius).max(radius));
+                charge.velocity.x *= -restitution;
+            }
+            if charge.position.y < radius || charge.position.y > box_size.y - radius {
+                charge.position.y = charge.position.y.clamp(radius, (box_size.y - radius).max(radius));
+                charge.velocity.y *= -restitution;
+            }
+        }
+    }
+
+    /// Samples the potential and the field on the grid, empty unless the potential is shown.
+    pub fn compute_field_grid(&self) -> FieldGrid {
+        if !self.show_potential {
+            return FieldGrid::default();
+        }
+
+        self.field.field_grid(&self.sources(), self.box_size, self.grid_cell_size)
+    }
+
+    /// Colors the potential of `grid` as RGBA pixels.
+    ///
+    /// The potential is compressed with a hyperbolic tangent, the middle of the gradient being a zero
+    /// potential and its ends reached around the potential of a unit charge seen from 10 charge radii.
+    pub fn potential_pixels(&self, grid: &FieldGrid) -> Vec<u8> {
+        let potential_scale = self.field.coulomb_constant / (10.0 * self.charge_radius);
+
+        grid.potentials.iter().flat_map(|potential| {
+            let normalized_potential = 0.5 + 0.5 * (potential / potential_scale).tanh();
+            self.potential_gradient.at(normalized_potential as f64).to_rgba8()
+        }).collect()
+    }
+
+    /// Traces the field lines, empty unless they are shown.
+    pub fn compute_field_lines(&self) -> Vec<Vec<Vector2>> {
+        if !self.show_field_lines {
+            return Vec::new();
+        }
+
+        self.field.field_lines(&self.sources(), self.box_size, self.lines_per_charge, 4.0, 1000)
+    }
+
+    /// Computes the field grid, its pixels and the field lines again if they are outdated or a charge
+    /// has moved by more than `RENDER_TOLERANCE` since they were computed.
+    pub fn update_render_data(&mut self) {
+        let moved = self.rendered_positions.len() != self.charges.len()
+            || self.charges.iter().zip(self.rendered_positions.iter()).any(|(charge, position)| charge.position.distance_to(*position) > RENDER_TOLERANCE);
+        if !self.render_outdated && !moved {
+            return;
+        }
+
+        self.field_grid = self.compute_field_grid();
+        self.pixels = self.potential_pixels(&self.field_grid);
+        self.field_lines = self.compute_field_lines();
+        self.rendered_positions = self.charges.iter().map(|charge| charge.position).collect();
+        self.render_outdated = false;
+    }
+}
@@ -0,0 +1,156 @@
+use crate::core::app_system::simulation::template::SimulationTemplate;
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::sciences::maths::vector2::Vector2;
+
+use crate::core::sciences::physics::force_fields::handle_force_field_event;
+
+use super::main::Electrostatics;
+use super::data::*;
+
+/// Obligatory implementation of the `SimulationTemplate` trait for the `Electrostatics` struct.
+impl SimulationTemplate for Electrostatics {
+    fn initialize(&mut self, renderer_size: Vector2, serialized_data: Option<String>) -> Result<(), String> {
+        self.clear();
+        self.box_size = renderer_size;
+        self.render_outdated = true;
+
+        let starter_data: StarterData = match serialized_data {
+            Some(data) => match serde_json::from_str(&data) {
+                Ok(deserialized_data) => deserialized_data,
+                Err(e) => return Err(e.to_string())
+            },
+            None => return Ok(())
+        };
+
+        for charge in &starter_data.charges {
+            self.add_charge(charge)?;
+        }
+        self.update_render_data();
+
+        Ok(())
+    }
+
+    fn next_step(&mut self, dt: f32) -> Result<(), String> {
+        self.update(dt);
+        self.update_render_data();
+
+        Ok(())
+    }
+
+    fn get_data_to_render(&self) -> Result<Box<dyn DataToSend>, String> {
+        let data_to_render = RendererData {
+            charges: self.charges.clone(),
+            charge_radius: self.charge_radius,
+            field_grid: self.field_grid.clone(),
+            pixels: self.pixels.clone(),
+            field_lines: self.field_lines.clone(),
+            force_fields: self.force_fields.clone()
+        };
+
+        Ok(Box::new(data_to_render))
+    }
+
+    fn event_handler(&mut self, event: String, data: Option<String>) -> Result<(), String> {
+        match event.as_str() {
+            "set_settings" => {
+                match data {
+                    Some(data) => {
+                        let settings: EventSettings = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.field.coulomb_constant = settings.coulomb_constant.max(0.0);
+                        self.field.softening = settings.softening.max(0.1);
+                        self.field.external_field = settings.external_field;
+                        self.damping = settings.damping.max(0.0);
+                        self.restitution = settings.restitution.clamp(0.0, 1.0);
+                        self.friction = settings.friction.max(0.0);
+                        self.show_potential = settings.show_potential;
+                        self.show_field_lines = settings.show_field_lines;
+                        self.grid_cell_size = settings.grid_cell_size.max(2.0);
+                        self.lines_per_charge = settings.lines_per_charge.min(64);
+                        self.accelerations.clear();
+                        self.render_outdated = true;
+                    },
+                    None => return Err("No data provided for the 'set_settings' event".to_string())
+                }
+            },
+            "add_charge" => {
+                match data {
+                    Some(data) => {
+                        let charge: EventAddCharge = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.add_charge(&charge)?;
+                    },
+                    None => return Err("No data provided for the 'add_charge' event".to_string())
+                }
+            },
+            "remove_charge" => {
+                match data {
+                    Some(data) => {
+                        let index: usize = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.remove_charge(index)?;
+                    },
+                    None => return Err("No data provided for the 'remove_charge' event".to_string())
+                }
+            },
+            "grab_charge" => {
+                match data {
+                    Some(data) => {
+                        let pointer: EventPointer = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        // Grabbing the empty space does nothing
+                        self.held_charge = self.find_charge_at(pointer.position);
+                        if let Some(index) = self.held_charge {
+                            self.charges[index].velocity = Vector2::zero();
+                            self.accelerations.clear();
+                        }
+                    },
+                    None => return Err("No data provided for the 'grab_charge' event".to_string())
+                }
+            },
+            "move_grab" => {
+                match data {
+                    Some(data) => {
+                        let pointer: EventPointer = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        if let Some(index) = self.held_charge {
+                            self.charges[index].position = pointer.position;
+                        }
+                    },
+                    None => return Err("No data provided for the 'move_grab' event".to_string())
+                }
+            },
+            "release_charge" => {
+                self.held_charge = None;
+                self.accelerations.clear();
+            },
+            "clear" => {
+                self.clear();
+            },
+            "add_force_field" | "remove_force_field" | "clear_force_fields" => {
+                handle_force_field_event(&mut self.force_fields, &event, data)?;
+            },
+            _ => return Err("Unknown event".to_string())
+        }
+
+        // Keeps the render data up to date while the simulation is paused
+        self.update_render_data();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Serialize, Deserialize};
+
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::apps::fluid::main::FluidParticles;
+use crate::core::apps::fluid::visualization::{ColorRange, VisualFilter};
+use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::physics::force_fields::ForceField;
+
+/// Same particle data as the SPH fluid, so that the frontend draws both the same way.
+#[derive(Serialize, Clone)]
+pub struct RendererData {
+    pub fluid_particles: FluidParticles,
+    pub displayed_range: (f32, f32),
+    pub force_fields: Vec<ForceField>,
+}
+
+impl DataToSend for RendererData {}
+
+#[derive(Deserialize, Clone)]
+pub struct StarterData {
+    pub positions: Vec<Vector2>
+}
+
+#[derive(Deserialize)]
+pub struct EventSettings {
+    pub gravity: f32,
+    pub flip_ratio: f32,
+    pub pressure_iterations: usize,
+    pub over_relaxation: f32,
+    pub separation_iterations: usize,
+    pub compensate_drift: bool,
+    pub visual_filter: VisualFilter,
+    pub color_range: ColorRange,
+    pub interactive_force_mode: bool
+}
@@ -0,0 +1,478 @@
+use colorgrad::Gradient;
+
+use crate::core::apps::fluid::main::FluidParticles;
+use crate::core::apps::fluid::visualization::{ColorRange, VisualFilter, displayed_range};
+use crate::core::sciences::maths::Vector2;
+use crate::core::sciences::physics::force_fields::{ForceField, total_acceleration};
+
+/// Type of a cell of the MAC grid, updated from the particles at each step.
+#[derive(Clone, Copy, PartialEq)]
+enum CellType {
+    Fluid,
+    Air,
+    Solid,
+}
+
+/// A liquid simulated with the FLIP/PIC hybrid method (Zhu & Bridson 2005).
+///
+/// The particles carry the liquid and its velocity, while the pressure is solved on a staggered
+/// MAC grid: horizontal velocities are stored on the left face of each cell and vertical ones
+/// on its top face. At each step the particle velocities are transferred to the grid, made
+/// divergence free, then transferred back as a blend of the new grid velocity (PIC, stable but
+/// viscous) and of the grid velocity change added to the particle velocity (FLIP, lively but noisy).
+pub struct FlipFluid {
+    // FLUID PROPERTIES
+    pub particles: FluidParticles,
+    pub gravity: f32,
+    /// External force fields, added to the gravity.
+    pub force_fields: Vec<ForceField>,
+    pub flip_ratio: f32,
+    pub visual_filter: VisualFilter,
+    pub color_range: ColorRange,
+    pub displayed_range: (f32, f32),
+    pub interactive_force: bool,
+    pub interactive_force_position: Vector2,
+    pub interactive_force_mode: bool,
+    // SOLVER PROPERTIES
+    pub pressure_iterations: usize,
+    pub over_relaxation: f32,
+    pub separation_iterations: usize,
+    pub compensate_drift: bool,
+    // GRID PROPERTIES
+    pub box_bound_x: f32,
+    pub box_bound_y: f32,
+    pub cell_size: f32,
+    pub grid_width: usize,
+    pub grid_height: usize,
+    velocities_x: Vec<f32>,
+    velocities_y: Vec<f32>,
+    previous_velocities_x: Vec<f32>,
+    previous_velocities_y: Vec<f32>,
+    cell_types: Vec<CellType>,
+    cell_densities: Vec<f32>,
+    rest_density: f32,
+    // OTHER PROPERTIES
+    pub velocity_gradient: Gradient,
+    pub density_gradient: Gradient
+}
+
+impl FlipFluid {
+    pub fn new(velocity_gradient: Gradient) -> Self {
+        FlipFluid {
+            // FLUID PROPERTIES
+            particles: FluidParticles::new(1.0, 5.0, 0.0, 0.0, 0.0),
+            gravity: 100.0,
+            force_fields: Vec::new(),
+            flip_ratio: 0.9,
+            visual_filter: VisualFilter::None,
+            color_range: ColorRange::Auto,
+            displayed_range: (0.0, 1.0),
+            interactive_force: false,
+            interactive_force_position: Vector2::zero(),
+            interactive_force_mode: true,
+            // SOLVER PROPERTIES
+            pressure_iterations: 50,
+            over_relaxation: 1.9,
+            separation_iterations: 2,
+            compensate_drift: true,
+            // GRID PROPERTIES
+            box_bound_x: 800.0,
+            box_bound_y: 600.0,
+            cell_size: 0.0,
+            grid_width: 0,
+            grid_height: 0,
+            velocities_x: Vec::new(),
+            velocities_y: Vec::new(),
+            previous_velocities_x: Vec::new(),
+            previous_velocities_y: Vec::new(),
+            cell_types: Vec::new(),
+            cell_densities: Vec::new(),
+            rest_density: 0.0,
+            // OTHER PROPERTIES
+            velocity_gradient,
+            density_gradient: colorgrad::CustomGradient::new().html_colors(&["#0077ff", "#ffffff", "ff3131"]).domain(&[0.0, 0.5, 1.0]).build().unwrap()
+        }
+    }
+
+    /// Allocates the grid covering the box, with cells large enough to hold a few particles
+    /// and a ring of solid cells along the walls.
+    pub fn resize(&mut self, size: Vector2) {
+        self.box_bound_x = size.x;
+        self.box_bound_y = size.y;
+        self.cell_size = self.particles.radius / 0.3;
+        self.grid_width = (size.x / self.cell_size).floor().max(3.0) as usize + 1;
+        self.grid_height = (size.y / self.cell_size).floor().max(3.0) as usize + 1;
+
+        let cell_count = self.grid_width * self.grid_height;
+        self.velocities_x = vec![0.0; cell_count];
+        self.velocities_y = vec![0.0; cell_count];
+        self.previous_velocities_x = vec![0.0; cell_count];
+        self.previous_velocities_y = vec![0.0; cell_count];
+        self.cell_types = vec![CellType::Air; cell_count];
+        self.cell_densities = vec![0.0; cell_count];
+        // Particle density of a cell filled with particles spaced by one diameter
+        self.rest_density = (self.cell_size / (2.0 * self.particles.radius)).powi(2);
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        x + y * self.grid_width
+    }
+
+    fn is_wall(&self, x: usize, y: usize) -> bool {
+        x == 0 || y == 0 || x == self.grid_width - 1 || y == self.grid_height - 1
+    }
+
+    /// Returns the cell containing `position`, clamped to the grid.
+    fn cell_of(&self, position: Vector2) -> (usize, usize) {
+        let x = ((position.x / self.cell_size).floor().max(0.0) as usize).min(self.grid_width - 1);
+        let y = ((position.y / self.cell_size).floor().max(0.0) as usize).min(self.grid_height - 1);
+        (x, y)
+    }
+
+    /// Returns the 4 grid nodes around `position` and their bilinear weights, for nodes shifted by `shift`.
+    fn bilinear_nodes(&self, position: Vector2, shift: Vector2) -> [(usize, f32); 4] {
+        let h = self.cell_size;
+        let x = position.x.clamp(h, (self.grid_width - 1) as f32 * h) - shift.x;
+        let y = position.y.clamp(h, (self.grid_height - 1) as f32 * h) - shift.y;
+
+        let x0 = ((x / h).floor() as usize).min(self.grid_width - 2);
+        let y0 = ((y / h).floor() as usize).min(self.grid_height - 2);
+        let tx = (x - x0 as f32 * h) / h;
+        let ty = (y - y0 as f32 * h) / h;
+        let (x1, y1) = ((x0 + 1).min(self.grid_width - 2), (y0 + 1).min(self.grid_height - 2));
+
+        [
+            (self.index(x0, y0), (1.0 - tx) * (1.0 - ty)),
+            (self.index(x1, y0), tx * (1.0 - ty)),
+            (self.index(x1, y1), tx * ty),
+            (self.index(x0, y1), (1.0 - tx) * ty),
+        ]
+    }
+
+    /// Applies gravity, the force fields and the interactive force, then moves the particles.
+    fn integrate_particles(&mut self, dt: f32) {
+        (0..self.particles.len()).for_each(|i| {
+            self.particles.velocities[i] += Vector2::down() * self.gravity * dt;
+            let acceleration = total_acceleration(&self.force_fields, self.particles.positions[i], self.particles.velocities[i]);
+            self.particles.velocities[i] += acceleration * dt;
+
+            if self.interactive_force {
+                let interaction_force = self.calculate_interaction_force(self.interactive_force_position, 100.0, 150.0, i);
+                self.particles.velocities[i] += interaction_force;
+            }
+
+            self.particles.positions[i] += self.particles.velocities[i] * dt;
+        });
+    }
+
+    /// Same pointer interaction as the SPH fluid, relaxing the velocity towards the pointer.
+    fn calculate_interaction_force(&self, position: Vector2, radius: f32, strength: f32, i: usize) -> Vector2 {
+        let mut interaction_force = Vector2::zero();
+        let dst = self.particles.positions[i].distance_to(position);
+
+        if dst < radius {
+            let direction = if dst <= f32::EPSILON { Vector2::zero() } else { (position - self.particles.positions[i]).normalize().unwrap() };
+            let center_t = 1.0 - dst / radius;
+            interaction_force += (direction * strength - self.particles.velocities[i]) * center_t;
+        }
+
+        interaction_force * if self.interactive_force_mode { 1.0 } else { -0.15 }
+    }
+
+    /// Pushes overlapping particles apart, which keeps them evenly spread in the cells.
+    fn separate_particles(&mut self) {
+        let radius = self.particles.radius;
+        let min_distance = 2.0 * radius;
+        let spacing = 2.2 * radius;
+        let columns = (self.box_bound_x / spacing).floor() as usize + 1;
+        let rows = (self.box_bound_y / spacing).floor() as usize + 1;
+        let cell_of = |position: Vector2| -> (usize, usize) {
+            (((position.x / spacing).max(0.0) as usize).min(columns - 1), ((position.y / spacing).max(0.0) as usize).min(rows - 1))
+        };
+
+        // Sort the particles by cell
+        let mut cell_starts = vec![0usize; columns * rows + 1];
+        for position in self.particles.positions.iter() {
+            let (x, y) = cell_of(*position);
+            cell_starts[x + y * columns] += 1;
+        }
+        let mut start = 0;
+        for count in cell_starts.iter_mut() {
+            start += *count;
+            *count = start;
+        }
+        let mut sorted_ids = vec![0usize; self.particles.len()];
+        for (i, position) in self.particles.positions.iter().enumerate() {
+            let (x, y) = cell_of(*position);
+            cell_starts[x + y * columns] -= 1;
+            sorted_ids[cell_starts[x + y * columns]] = i;
+        }
+
+        for _ in 0..self.separation_iterations {
+            for i in 0..self.particles.len() {
+                let (cell_x, cell_y) = cell_of(self.particles.positions[i]);
+
+                for y in cell_y.saturating_sub(1)..=(cell_y + 1).min(rows - 1) {
+                    for x in cell_x.saturating_sub(1)..=(cell_x + 1).min(columns - 1) {
+                        let cell = x + y * columns;
+                        for &other_i in sorted_ids[cell_starts[cell]..cell_starts[cell + 1]].iter() {
+                            if other_i == i {
+                                continue;
+                            }
+
+                            let offset = self.particles.positions[other_i] - self.particles.positions[i];
+                            let distance = offset.magnitude();
+                            if distance >= min_distance || distance <= f32::EPSILON {
+                                continue;
+                            }
+
+                            let correction = offset * (0.5 * (min_distance - distance) / distance);
+                            self.particles.positions[i] -= correction;
+                            self.particles.positions[other_i] += correction;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Keeps the particles inside the fluid cells, cancelling the velocity going into the walls.
+    fn resolve_collisions(&mut self) {
+        let min_x = self.cell_size + self.particles.radius;
+        let max_x = (self.grid_width - 1) as f32 * self.cell_size - self.particles.radius;
+        let min_y = self.cell_size + self.particles.radius;
+        let max_y = (self.grid_height - 1) as f32 * self.cell_size - self.particles.radius;
+
+        (0..self.particles.len()).for_each(|i| {
+            let position = &mut self.particles.positions[i];
+            let velocity = &mut self.particles.velocities[i];
+
+            if position.x < min_x {
+                position.x = min_x;
+                velocity.x = 0.0;
+            } else if position.x > max_x {
+                position.x = max_x;
+                velocity.x = 0.0;
+            }
+
+            if position.y < min_y {
+                position.y = min_y;
+                velocity.y = 0.0;
+            } else if position.y > max_y {
+                position.y = max_y;
+                velocity.y = 0.0;
+            }
+        });
+    }
+
+    /// Splats the particles on the cell centers, giving the particle density used to compensate the drift.
+    fn update_cell_densities(&mut self) {
+        let mut cell_densities = vec![0.0; self.cell_densities.len()];
+        let shift = Vector2::new(0.5, 0.5) * self.cell_size;
+
+        for position in self.particles.positions.iter() {
+            for (node, weight) in self.bilinear_nodes(*position, shift) {
+                cell_densities[node] += weight;
+            }
+        }
+
+        self.cell_densities = cell_densities;
+    }
+
+    /// Marks the cells as solid, fluid or air, then transfers the particle velocities to the faces.
+    fn transfer_to_grid(&mut self) {
+        self.previous_velocities_x.clone_from(&self.velocities_x);
+        self.previous_velocities_y.clone_from(&self.velocities_y);
+
+        for y in 0..self.grid_height {
+            for x in 0..self.grid_width {
+                let i = self.index(x, y);
+                self.cell_types[i] = if self.is_wall(x, y) { CellType::Solid } else { CellType::Air };
+            }
+        }
+        for position in self.particles.positions.iter() {
+            let (x, y) = self.cell_of(*position);
+            let i = self.index(x, y);
+            if self.cell_types[i] == CellType::Air {
+                self.cell_types[i] = CellType::Fluid;
+            }
+        }
+
+        let cell_count = self.grid_width * self.grid_height;
+        let (mut velocities_x, mut velocities_y) = (vec![0.0; cell_count], vec![0.0; cell_count]);
+        let (mut weights_x, mut weights_y) = (vec![0.0; cell_count], vec![0.0; cell_count]);
+        let shift_x = Vector2::new(0.0, 0.5 * self.cell_size);
+        let shift_y = Vector2::new(0.5 * self.cell_size, 0.0);
+
+        for (position, velocity) in self.particles.positions.iter().zip(self.particles.velocities.iter()) {
+            for (node, weight) in self.bilinear_nodes(*position, shift_x) {
+                velocities_x[node] += velocity.x * weight;
+                weights_x[node] += weight;
+            }
+            for (node, weight) in self.bilinear_nodes(*position, shift_y) {
+                velocities_y[node] += velocity.y * weight;
+                weights_y[node] += weight;
+            }
+        }
+
+        for i in 0..cell_count {
+            if weights_x[i] > 0.0 {
+                velocities_x[i] /= weights_x[i];
+            }
+            if weights_y[i] > 0.0 {
+                velocities_y[i] /= weights_y[i];
+            }
+        }
+
+        // Faces touching a solid cell keep the velocity of the solid
+        for y in 0..self.grid_height {
+            for x in 0..self.grid_width {
+                let i = self.index(x, y);
+                let solid = self.cell_types[i] == CellType::Solid;
+                if solid || (x > 0 && self.cell_types[i - 1] == CellType::Solid) {
+                    velocities_x[i] = self.previous_velocities_x[i];
+                }
+                if solid || (y > 0 && self.cell_types[i - self.grid_width] == CellType::Solid) {
+                    velocities_y[i] = self.previous_velocities_y[i];
+                }
+            }
+        }
+
+        self.velocities_x = velocities_x;
+        self.velocities_y = velocities_y;
+        self.previous_velocities_x.clone_from(&self.velocities_x);
+        self.previous_velocities_y.clone_from(&self.velocities_y);
+    }
+
+    /// Makes the grid velocity divergence free with over-relaxed Gauss-Seidel iterations,
+    /// removing as well the excess of particle density when `compensate_drift` is set.
+    fn solve_incompressibility(&mut self, dt: f32) {
+        let fluidity = |cell_type: CellType| if cell_type == CellType::Solid { 0.0 } else { 1.0 };
+
+        for _ in 0..self.pressure_iterations {
+            for y in 1..self.grid_height - 1 {
+                for x in 1..self.grid_width - 1 {
+                    let i = self.index(x, y);
+                    if self.cell_types[i] != CellType::Fluid {
+                        continue;
+                    }
+
+                    let (left, right) = (i - 1, i + 1);
+                    let (top, bottom) = (i - self.grid_width, i + self.grid_width);
+                    let (s_left, s_right) = (fluidity(self.cell_types[left]), fluidity(self.cell_types[right]));
+                    let (s_top, s_bottom) = (fluidity(self.cell_types[top]), fluidity(self.cell_types[bottom]));
+                    let s = s_left + s_right + s_top + s_bottom;
+                    if s == 0.0 {
+                        continue;
+                    }
+
+                    let mut divergence = self.velocities_x[right] - self.velocities_x[i] + self.velocities_y[bottom] - self.velocities_y[i];
+
+                    if self.compensate_drift && self.rest_density > 0.0 {
+                        let compression = self.cell_densities[i] - self.rest_density;
+                        if compression > 0.0 {
+                            // Velocity removing the compression over one step
+                            divergence -= compression / self.rest_density * self.cell_size / dt;
+                        }
+                    }
+
+                    let pressure = -divergence / s * self.over_relaxation;
+                    self.velocities_x[i] -= s_left * pressure;
+                    self.velocities_x[right] += s_right * pressure;
+                    self.velocities_y[i] -= s_top * pressure;
+                    self.velocities_y[bottom] += s_bottom * pressure;
+                }
+            }
+        }
+    }
+
+    /// Transfers the grid velocity back to the particles, blending PIC and FLIP with `flip_ratio`.
+    fn transfer_to_particles(&mut self) {
+        let shift_x = Vector2::new(0.0, 0.5 * self.cell_size);
+        let shift_y = Vector2::new(0.5 * self.cell_size, 0.0);
+
+        (0..self.particles.len()).for_each(|i| {
+            let position = self.particles.positions[i];
+            let velocity = self.particles.velocities[i];
+
+            // Only the faces next to a non-air cell carry a meaningful velocity
+            let valid_x = |node: usize| self.cell_types[node] != CellType::Air || (node >= 1 && self.cell_types[node - 1] != CellType::Air);
+            let valid_y = |node: usize| self.cell_types[node] != CellType::Air || (node >= self.grid_width && self.cell_types[node - self.grid_width] != CellType::Air);
+
+            let new_x = self.interpolate(self.bilinear_nodes(position, shift_x), valid_x, &self.velocities_x, &self.previous_velocities_x, velocity.x);
+            let new_y = self.interpolate(self.bilinear_nodes(position, shift_y), valid_y, &self.velocities_y, &self.previous_velocities_y, velocity.y);
+
+            self.particles.velocities[i] = Vector2::new(new_x, new_y);
+        });
+    }
+
+    /// Interpolates a velocity component at the valid `nodes`, blending PIC and FLIP.
+    fn interpolate(&self, nodes: [(usize, f32); 4], valid: impl Fn(usize) -> bool, field: &[f32], previous_field: &[f32], particle_velocity: f32) -> f32 {
+        let mut total_weight = 0.0;
+        let mut pic_velocity = 0.0;
+        let mut velocity_change = 0.0;
+
+        for (node, weight) in nodes {
+            if !valid(node) {
+                continue;
+            }
+            total_weight += weight;
+            pic_velocity += field[node] * weight;
+            velocity_change += (field[node] - previous_field[node]) * weight;
+        }
+
+        if total_weight <= 0.0 {
+            return particle_velocity;
+        }
+
+        let pic_velocity = pic_velocity / total_weight;
+        let flip_velocity = particle_velocity + velocity_change / total_weight;
+
+        (1.0 - self.flip_ratio) * pic_velocity + self.flip_ratio * flip_velocity
+    }
+
+    /// Colours the particles according to the visual filter, mapping `color_range` on the gradient like the SPH fluid.
+    fn update_colors(&mut self) {
+        let values: Vec<f32> = match self.visual_filter {
+            VisualFilter::None => return,
+            VisualFilter::Speed => self.particles.velocities.iter().map(|velocity| velocity.magnitude()).collect(),
+            VisualFilter::Density => {
+                (0..self.particles.len()).for_each(|i| {
+                    let (x, y) = self.cell_of(self.particles.positions[i]);
+                    self.particles.densities[i] = if self.rest_density > 0.0 { self.cell_densities[self.index(x, y)] / self.rest_density } else { 0.0 };
+                });
+                self.particles.densities.clone()
+            },
+            // The other filters need quantities the grid solver does not compute
+            _ => return,
+        };
+
+        let (min, max) = displayed_range(self.color_range, &values, false);
+        let span = (max - min).max(f32::EPSILON);
+        self.displayed_range = (min, max);
+
+        let gradient = match self.visual_filter {
+            VisualFilter::Density => &self.density_gradient,
+            _ => &self.velocity_gradient,
+        };
+
+        for (color, value) in self.particles.colors.iter_mut().zip(values.iter()) {
+            *color = gradient.at(((value - min) / span) as f64).to_hex_string();
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.grid_width < 3 || self.grid_height < 3 || dt <= 0.0 {
+            return;
+        }
+
+        self.integrate_particles(dt);
+        self.separate_particles();
+        self.resolve_collisions();
+        self.transfer_to_grid();
+        self.update_cell_densities();
+        self.solve_incompressibility(dt);
+        self.transfer_to_particles();
+        self.update_colors();
+    }
+}
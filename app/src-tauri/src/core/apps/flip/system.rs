@@ -0,0 +1,115 @@
+use crate::core::app_system::simulation::template::SimulationTemplate;
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::apps::fluid::visualization::VisualFilter;
+use crate::core::sciences::maths::vector2::Vector2;
+
+use crate::core::sciences::physics::force_fields::handle_force_field_event;
+
+use super::main::FlipFluid;
+use super::data::*;
+
+/// Obligatory implementation of the `SimulationTemplate` trait for the `FlipFluid` struct.
+impl SimulationTemplate for FlipFluid {
+    fn initialize(&mut self, renderer_size: Vector2, serialized_data: Option<String>) -> Result<(), String> {
+        self.resize(renderer_size);
+
+        let starter_data: StarterData = match serialized_data {
+            Some(data) => match serde_json::from_str(&data) {
+                Ok(deserialized_data) => deserialized_data,
+                Err(e) => return Err(e.to_string())
+            },
+            None => return Ok(())
+        };
+
+        for position in starter_data.positions {
+            self.particles.push(position);
+        }
+
+        Ok(())
+    }
+
+    fn next_step(&mut self, dt: f32) -> Result<(), String> {
+        self.update(dt);
+
+        Ok(())
+    }
+
+    fn get_data_to_render(&self) -> Result<Box<dyn DataToSend>, String> {
+        let data_to_render = RendererData {
+            fluid_particles: self.particles.clone(),
+            displayed_range: self.displayed_range,
+            force_fields: self.force_fields.clone()
+        };
+
+        Ok(Box::new(data_to_render))
+    }
+
+    fn event_handler(&mut self, event: String, data: Option<String>) -> Result<(), String> {
+        match event.as_str() {
+            "set_settings" => {
+                match data {
+                    Some(data) => {
+                        let settings: EventSettings = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        if !matches!(settings.visual_filter, VisualFilter::None | VisualFilter::Speed | VisualFilter::Density) {
+                            return Err(format!("The FLIP fluid cannot show the {:?} filter", settings.visual_filter));
+                        }
+
+                        self.gravity = settings.gravity;
+                        self.flip_ratio = settings.flip_ratio.clamp(0.0, 1.0);
+                        self.pressure_iterations = settings.pressure_iterations;
+                        self.over_relaxation = settings.over_relaxation;
+                        self.separation_iterations = settings.separation_iterations;
+                        self.compensate_drift = settings.compensate_drift;
+                        self.visual_filter = settings.visual_filter;
+                        self.color_range = settings.color_range;
+                        self.interactive_force_mode = settings.interactive_force_mode;
+                    },
+                    None => return Err("No data provided for the 'set_settings' event".to_string())
+                }
+
+                if self.visual_filter == VisualFilter::None {
+                    for color in self.particles.colors.iter_mut() {
+                        *color = "#FFFFFFFF".to_string();
+                    }
+                }
+            },
+            "interractive_force_toggle" => {
+                match data {
+                    Some(data) => {
+                        let interactive_force_toggle: bool = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.interactive_force = interactive_force_toggle;
+                    },
+                    None => return Err("No data provided for the 'interractive_force_toggle' event".to_string())
+                }
+            },
+            "interactive_force_position" => {
+                match data {
+                    Some(data) => {
+                        let interactive_force_position: Vector2 = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.interactive_force = true;
+                        self.interactive_force_position = interactive_force_position;
+                    },
+                    None => return Err("No data provided for the 'interactive_force_position' event".to_string())
+                }
+            },
+            "add_force_field" | "remove_force_field" | "clear_force_fields" => {
+                handle_force_field_event(&mut self.force_fields, &event, data)?;
+            },
+            _ => return Err("Unknown event".to_string())
+        }
+
+        Ok(())
+    }
+}
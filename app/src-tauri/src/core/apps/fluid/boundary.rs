@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use crate::core::sciences::maths::Vector2;
+
+/// Static particles sampling the walls and obstacles of the fluid domain (Akinci et al. 2012).
+///
+/// Boundary particles are never integrated, they only complete the neighbourhood of the fluid
+/// particles close to a boundary so that their density, and therefore their pressure, is not
+/// underestimated.
+pub struct BoundaryParticles {
+    pub positions: Vec<Vector2>,
+    /// Effective volume of each boundary particle, `1 / Σ W(x_b - x_k)` over its boundary neighbours.
+    pub volumes: Vec<f32>,
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl Default for BoundaryParticles {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BoundaryParticles {
+    pub fn new() -> Self {
+        Self {
+            positions: Vec::new(),
+            volumes: Vec::new(),
+            cell_size: 1.0,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.volumes.clear();
+        self.cells.clear();
+    }
+
+    /// Samples the segment `[a, b]` with particles separated by at most `spacing`, `b` excluded.
+    pub fn sample_segment(&mut self, a: Vector2, b: Vector2, spacing: f32) {
+        let length = a.distance_to(b);
+        let count = (length / spacing).ceil().max(1.0) as usize;
+
+        for k in 0..count {
+            self.positions.push(a + (b - a) * (k as f32 / count as f32));
+        }
+    }
+
+    /// Samples the closed polyline going through `vertices`.
+    pub fn sample_polygon(&mut self, vertices: &[Vector2], spacing: f32) {
+        for i in 0..vertices.len() {
+            self.sample_segment(vertices[i], vertices[(i + 1) % vertices.len()], spacing);
+        }
+    }
+
+    fn get_cell(&self, position: &Vector2) -> (i32, i32) {
+        ((position.x / self.cell_size).floor() as i32, (position.y / self.cell_size).floor() as i32)
+    }
+
+    /// Rebuilds the neighbour grid and computes the volume of every boundary particle.
+    ///
+    /// # Arguments
+    ///
+    /// * `smoothing_radius` - The smoothing radius of the fluid, used as the grid cell size.
+    /// * `kernel` - The density kernel of the fluid.
+    pub fn update(&mut self, smoothing_radius: f32, kernel: impl Fn(f32) -> f32) {
+        self.cell_size = smoothing_radius;
+        self.cells.clear();
+
+        for (i, position) in self.positions.iter().enumerate() {
+            let cell = self.get_cell(position);
+            self.cells.entry(cell).or_default().push(i);
+        }
+
+        self.volumes = (0..self.len()).map(|i| {
+            let weight: f32 = self.neighbors(self.positions[i], smoothing_radius)
+                .iter()
+                .map(|b| kernel(self.positions[i].distance_to(self.positions[*b])))
+                .sum();
+            if weight > 0.0 { 1.0 / weight } else { 0.0 }
+        }).collect();
+    }
+
+    /// Returns the indices of the boundary particles closer than `radius` to `position`.
+    pub fn neighbors(&self, position: Vector2, radius: f32) -> Vec<usize> {
+        let mut neighbors = Vec::new();
+        let (cx, cy) = self.get_cell(&position);
+        let sqr_radius = radius * radius;
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if let Some(indices) = self.cells.get(&(cx + dx, cy + dy)) {
+                    for b in indices {
+                        if position.distance_to_squared(self.positions[*b]) < sqr_radius {
+                            neighbors.push(*b);
+                        }
+                    }
+                }
+            }
+        }
+
+        neighbors
+    }
+}
@@ -2,30 +2,80 @@ use serde::{Serialize, Deserialize};
 
 use crate::core::app_system::simulation::renderer::DataToSend;
 use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::maths::integrators::Integrator;
 
-use super::main::FluidParticles;
+use super::main::{FluidParticles, FluidSolver, SolverMetrics, PeriodicBoundaries};
+use super::obstacle::Obstacle;
+use super::emitter::{Emitter, Sink};
+use super::thermal::ThermalRegion;
+use super::visualization::{VisualFilter, ColorRange};
+use super::surface::FluidSurface;
+use super::layout::FluidLayout;
+use super::kernel::KernelSettings;
+use crate::core::sciences::physics::force_fields::ForceField;
 
 #[derive(Serialize, Clone)]
 pub struct RendererData {
     pub fluid_particles: FluidParticles,
+    pub obstacles: Vec<Obstacle>,
+    pub emitters: Vec<Emitter>,
+    pub sinks: Vec<Sink>,
+    pub thermal_regions: Vec<ThermalRegion>,
+    pub metrics: SolverMetrics,
+    pub substeps: usize,
+    pub displayed_range: (f32, f32),
+    pub surface: Option<FluidSurface>,
+    pub force_fields: Vec<ForceField>,
 }
 
 impl DataToSend for RendererData {}
 
 #[derive(Deserialize, Clone)]
 pub struct StarterData {
-    pub positions: Vec<Vector2>
+    #[serde(default)]
+    pub positions: Vec<Vector2>,
+    /// Layouts generated by the backend, added to the explicit positions.
+    #[serde(default)]
+    pub layouts: Vec<FluidLayout>,
+    #[serde(default)]
+    pub obstacles: Vec<Obstacle>,
+    #[serde(default)]
+    pub emitters: Vec<Emitter>,
+    #[serde(default)]
+    pub sinks: Vec<Sink>,
+    #[serde(default)]
+    pub thermal_regions: Vec<ThermalRegion>
 }
 
 #[derive(Deserialize)]
 pub struct EventSettings {
     pub collision_restitution: f32,
     pub gravity: f32,
+    pub integrator: Integrator,
     pub target_density: f32,
     pub mass: f32,
     pub pressure_stiffness: f32,
-    pub visual_filter: u8,
+    pub visual_filter: VisualFilter,
+    pub color_range: ColorRange,
+    pub show_surface: bool,
+    pub surface_resolution: f32,
+    pub surface_iso_level: f32,
     pub smoothing_radius: f32,
     pub viscosity_strength: f32,
-    pub interactive_force_mode: bool
+    pub interactive_force_mode: bool,
+    pub surface_tension_strength: f32,
+    pub adhesion_strength: f32,
+    pub use_boundary_particles: bool,
+    pub periodic: PeriodicBoundaries,
+    pub solver: FluidSolver,
+    pub solver_iterations: usize,
+    pub kernels: KernelSettings,
+    pub max_density_error: f32,
+    pub thermal_diffusivity: f32,
+    pub thermal_expansion: f32,
+    pub viscoelastic: bool,
+    pub spring_stiffness: f32,
+    pub yield_ratio: f32,
+    pub plasticity: f32,
+    pub ambient_temperature: f32
 }
\ No newline at end of file
@@ -0,0 +1,88 @@
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use crate::core::sciences::maths::Vector2;
+
+use super::obstacle::ObstacleShape;
+
+/// A source continuously spawning fluid particles, e.g. a tap or the inlet of a river.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Emitter {
+    pub position: Vector2,
+    /// Direction of the emitted particles, normalized when particles are spawned.
+    pub direction: Vector2,
+    /// Number of particles emitted per second.
+    pub rate: f32,
+    /// Initial speed of the emitted particles.
+    pub speed: f32,
+    /// Phase tag given to the emitted particles, used to tell apart fluids from different sources.
+    pub phase: u8,
+    /// Width of the nozzle, particles are spawned uniformly across it.
+    #[serde(default)]
+    pub width: f32,
+    /// Fraction of particle left over from the previous steps.
+    #[serde(default, skip_serializing)]
+    accumulator: f32,
+}
+
+impl Emitter {
+    pub fn new(position: Vector2, direction: Vector2, rate: f32, speed: f32, phase: u8, width: f32) -> Self {
+        Self {
+            position,
+            direction,
+            rate,
+            speed,
+            phase,
+            width,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Checks that the rate of the emitter is a finite, non-negative number of particles per second.
+    pub fn check_rate(&self) -> Result<(), String> {
+        if !self.rate.is_finite() || self.rate < 0.0 {
+            return Err(format!("The rate of an emitter must be non-negative, got {}", self.rate));
+        }
+
+        Ok(())
+    }
+
+    /// Advances the emitter by `dt` and returns the position and velocity of the particles to spawn,
+    /// at most `max_count` of them, the particles over it being dropped.
+    pub fn emit(&mut self, dt: f32, max_count: usize) -> Vec<(Vector2, Vector2)> {
+        let direction = match self.direction.normalize() {
+            Ok(direction) => direction,
+            Err(_) => return Vec::new(),
+        };
+
+        self.accumulator += self.rate.max(0.0) * dt;
+        let count = (self.accumulator.floor() as usize).min(max_count);
+        self.accumulator -= self.accumulator.floor();
+
+        let mut rng = rand::thread_rng();
+        let velocity = direction * self.speed;
+
+        (0..count).map(|_| {
+            let offset = if self.width > 0.0 { rng.gen_range(-0.5..=0.5) * self.width } else { 0.0 };
+            (self.position + direction.perpendicular() * offset, velocity)
+        }).collect()
+    }
+}
+
+/// A region deleting every fluid particle entering it, e.g. a drain.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Sink {
+    pub shape: ObstacleShape,
+}
+
+impl Sink {
+    pub fn new(shape: ObstacleShape) -> Self {
+        Self {
+            shape
+        }
+    }
+
+    pub fn contains(&self, position: Vector2) -> bool {
+        self.shape.signed_distance(position).0 < 0.0
+    }
+}
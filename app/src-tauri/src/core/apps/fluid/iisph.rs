@@ -0,0 +1,235 @@
+use crate::core::sciences::maths::Vector2;
+
+use super::main::{Fluid, SolverMetrics};
+
+/// Relaxation factor of the Jacobi pressure iterations.
+const PRESSURE_RELAXATION: f32 = 0.5;
+
+/// Minimum number of pressure iterations, the residual of the first one being measured on zero pressures.
+const MIN_PRESSURE_ITERATIONS: usize = 2;
+
+/// Boundary particles around a fluid particle, as `(moving boundary index, position, volume)`.
+type BoundaryNeighbors = Vec<(Option<usize>, Vector2, f32)>;
+
+/// Implicit Incompressible SPH solver (Ihmsen et al. 2014).
+///
+/// The pressures are the solution of the pressure Poisson equation, found with relaxed Jacobi
+/// iterations until the average density error falls below `max_density_error`
+/// or `solver_iterations` iterations are done. Boundary particles take part in the solve with
+/// the rest density, the moving boundaries receiving the reaction of the pressure forces.
+impl Fluid {
+    /// Returns the velocity of a boundary particle, zero for the static ones.
+    fn boundary_velocity(&self, k: Option<usize>, position: Vector2) -> Vector2 {
+        match k {
+            Some(k) => self.moving_boundaries[k].velocity_at(position),
+            None => Vector2::zero(),
+        }
+    }
+
+    /// Computes the displacement factor `d_ii` of particle `i`, the displacement caused by its own pressure.
+    fn calculate_displacement_factor(&self, i: usize, neighbors: &[usize], boundaries: &BoundaryNeighbors, rest_density: f32, dt: f32) -> Vector2 {
+        let position = self.particles.positions[i];
+        let scale = dt * dt / (self.particles.densities[i] * self.particles.densities[i]);
+        let mut factor = Vector2::zero();
+
+        for other_i in neighbors {
+            factor -= self.kernel_gradient(self.particles.offset(position, self.particles.positions[*other_i])) * (self.particles.mass * scale);
+        }
+
+        for (_, boundary_position, volume) in boundaries {
+            factor -= self.kernel_gradient(position - *boundary_position) * (rest_density * volume * scale);
+        }
+
+        factor
+    }
+
+    /// Predicts the density of particle `i` after advection with the non-pressure forces only.
+    fn calculate_advected_density(&self, i: usize, neighbors: &[usize], boundaries: &BoundaryNeighbors, rest_density: f32, dt: f32) -> f32 {
+        let position = self.particles.positions[i];
+        let velocity = self.particles.velocities[i];
+        let mut density = self.particles.densities[i];
+
+        for other_i in neighbors {
+            let gradient = self.kernel_gradient(self.particles.offset(position, self.particles.positions[*other_i]));
+            density += (velocity - self.particles.velocities[*other_i]) * gradient * (dt * self.particles.mass);
+        }
+
+        for (k, boundary_position, volume) in boundaries {
+            let gradient = self.kernel_gradient(position - *boundary_position);
+            density += (velocity - self.boundary_velocity(*k, *boundary_position)) * gradient * (dt * rest_density * volume);
+        }
+
+        density
+    }
+
+    /// Computes the diagonal element `a_ii` of the pressure system of particle `i`.
+    fn calculate_diagonal(&self, i: usize, neighbors: &[usize], boundaries: &BoundaryNeighbors, displacement_factors: &[Vector2], rest_density: f32, dt: f32) -> f32 {
+        let position = self.particles.positions[i];
+        let scale = dt * dt * self.particles.mass / (self.particles.densities[i] * self.particles.densities[i]);
+        let mut diagonal = 0.0;
+
+        for other_i in neighbors {
+            let gradient = self.kernel_gradient(self.particles.offset(position, self.particles.positions[*other_i]));
+            // Displacement of the neighbour caused by the pressure of particle i
+            let displacement = gradient * scale;
+            diagonal += (displacement_factors[i] - displacement) * gradient * self.particles.mass;
+        }
+
+        for (_, boundary_position, volume) in boundaries {
+            let gradient = self.kernel_gradient(position - *boundary_position);
+            diagonal += displacement_factors[i] * gradient * (rest_density * volume);
+        }
+
+        diagonal
+    }
+
+    /// Computes `Σ_j d_ij p_j` for particle `i`, the displacement caused by the pressure of its neighbours.
+    fn calculate_pressure_displacement(&self, i: usize, neighbors: &[usize], pressures: &[f32], dt: f32) -> Vector2 {
+        let position = self.particles.positions[i];
+        let mut displacement = Vector2::zero();
+
+        for other_i in neighbors {
+            let density = self.particles.densities[*other_i];
+            let gradient = self.kernel_gradient(self.particles.offset(position, self.particles.positions[*other_i]));
+            displacement -= gradient * (dt * dt * self.particles.mass * pressures[*other_i] / (density * density));
+        }
+
+        displacement
+    }
+
+    /// Computes the pressure acceleration of particle `i`, and applies its reaction on the moving boundaries.
+    fn apply_implicit_pressure(&mut self, i: usize, neighbors: &[usize], boundaries: &BoundaryNeighbors, pressures: &[f32], rest_density: f32) -> Vector2 {
+        let position = self.particles.positions[i];
+        let pressure = pressures[i] / (self.particles.densities[i] * self.particles.densities[i]);
+        let mut acceleration = Vector2::zero();
+
+        for other_i in neighbors {
+            let density = self.particles.densities[*other_i];
+            let gradient = self.kernel_gradient(self.particles.offset(position, self.particles.positions[*other_i]));
+            acceleration -= gradient * (self.particles.mass * (pressure + pressures[*other_i] / (density * density)));
+        }
+
+        for (k, boundary_position, volume) in boundaries {
+            let boundary_acceleration = self.kernel_gradient(position - *boundary_position) * (-rest_density * volume * pressure);
+            acceleration += boundary_acceleration;
+
+            if let Some(k) = k {
+                self.moving_boundaries[*k].apply_force(boundary_acceleration * -self.particles.mass, *boundary_position);
+            }
+        }
+
+        acceleration
+    }
+
+    /// Performs a step of Implicit Incompressible SPH.
+    pub fn update_implicit(&mut self, dt: f32) {
+        if dt <= 0.0 {
+            return;
+        }
+
+        self.apply_external_forces(dt);
+
+        // The pressure system is built at the current positions
+        self.particles.predicted_positions.clone_from(&self.particles.positions);
+        self.particles.update_spatial_lookup();
+
+        if !self.moving_boundaries.is_empty() {
+            self.sample_moving_boundaries();
+        }
+
+        let particle_count = self.particles.len();
+        let rest_density = self.lattice_rest_density();
+        let neighbors: Vec<Vec<usize>> = (0..particle_count).map(|i| self.in_radius_neighbors_search(i)).collect();
+        let boundaries: Vec<BoundaryNeighbors> = (0..particle_count).map(|i| self.boundary_neighbors(self.particles.positions[i])).collect();
+
+        // Calculate densities
+        (0..particle_count).for_each(|i| {
+            self.particles.densities[i] = self.calculate_predicted_density(i, &neighbors[i], rest_density);
+        });
+
+        // Apply the non-pressure forces
+        let viscosity: Vec<Vector2> = (0..particle_count).map(|i| self.calculate_viscosity_force(i)).collect();
+        (0..particle_count).for_each(|i| {
+            self.particles.velocities[i] += viscosity[i] * dt;
+        });
+
+        self.apply_surface_forces(dt);
+        self.apply_heat_transfer(dt);
+        self.apply_springs(dt);
+
+        // Build the pressure system
+        let displacement_factors: Vec<Vector2> = (0..particle_count)
+            .map(|i| self.calculate_displacement_factor(i, &neighbors[i], &boundaries[i], rest_density, dt))
+            .collect();
+        let advected_densities: Vec<f32> = (0..particle_count)
+            .map(|i| self.calculate_advected_density(i, &neighbors[i], &boundaries[i], rest_density, dt))
+            .collect();
+        let diagonals: Vec<f32> = (0..particle_count)
+            .map(|i| self.calculate_diagonal(i, &neighbors[i], &boundaries[i], &displacement_factors, rest_density, dt))
+            .collect();
+
+        // Solve it with relaxed Jacobi iterations
+        let max_iterations = self.solver_iterations.max(MIN_PRESSURE_ITERATIONS);
+        let mut pressures = vec![0.0; particle_count];
+        let mut metrics = SolverMetrics::default();
+
+        while metrics.iterations < max_iterations {
+            let pressure_displacements: Vec<Vector2> = (0..particle_count)
+                .map(|i| self.calculate_pressure_displacement(i, &neighbors[i], &pressures, dt))
+                .collect();
+
+            let mut total_error = 0.0;
+            let mut next_pressures = vec![0.0; particle_count];
+
+            for i in 0..particle_count {
+                let position = self.particles.positions[i];
+                let scale = dt * dt * self.particles.mass / (self.particles.densities[i] * self.particles.densities[i]);
+                let mut sum = 0.0;
+
+                for other_i in neighbors[i].iter() {
+                    let gradient = self.kernel_gradient(self.particles.offset(position, self.particles.positions[*other_i]));
+                    // Displacement of the neighbour caused by every pressure but the ones of particle i and itself
+                    let other_displacement = pressure_displacements[*other_i] - gradient * (scale * pressures[i]);
+                    sum += (pressure_displacements[i] - displacement_factors[*other_i] * pressures[*other_i] - other_displacement) * gradient * self.particles.mass;
+                }
+
+                for (_, boundary_position, volume) in boundaries[i].iter() {
+                    let gradient = self.kernel_gradient(position - *boundary_position);
+                    sum += pressure_displacements[i] * gradient * (rest_density * volume);
+                }
+
+                // Only the compression is corrected, like the clamped pressures
+                let predicted_density = advected_densities[i] + diagonals[i] * pressures[i] + sum;
+                total_error += (predicted_density - rest_density).max(0.0);
+
+                if diagonals[i].abs() > f32::EPSILON {
+                    let pressure = (1.0 - PRESSURE_RELAXATION) * pressures[i] + PRESSURE_RELAXATION / diagonals[i] * (rest_density - advected_densities[i] - sum);
+                    next_pressures[i] = pressure.max(0.0);
+                }
+            }
+
+            pressures = next_pressures;
+            metrics.iterations += 1;
+            metrics.density_error = if particle_count > 0 { total_error / (particle_count as f32 * rest_density) } else { 0.0 };
+
+            if metrics.iterations >= MIN_PRESSURE_ITERATIONS && metrics.density_error <= self.max_density_error {
+                break;
+            }
+        }
+
+        self.metrics = metrics;
+        self.particles.pressures.clone_from(&pressures);
+
+        // Apply the pressure forces, update positions and resolve collisions
+        let pressure_accelerations: Vec<Vector2> = (0..particle_count)
+            .map(|i| self.apply_implicit_pressure(i, &neighbors[i], &boundaries[i], &pressures, rest_density))
+            .collect();
+
+        (0..particle_count).for_each(|i| {
+            self.particles.velocities[i] += pressure_accelerations[i] * dt;
+            self.advance_position(i, dt);
+            self.resolve_collision(i);
+            self.resolve_moving_boundary_collision(i, dt);
+        });
+    }
+}
@@ -0,0 +1,165 @@
+use std::f32::consts::PI;
+
+use serde::{Serialize, Deserialize};
+
+/// Radially symmetric SPH smoothing kernels with compact support, normalized in 2D.
+///
+/// Every kernel is written for a support radius `h`: it vanishes for `r >= h`
+/// and integrates to 1 over the disc of radius `h`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Kernel {
+    /// `(h² - r²)³`, smooth but with a vanishing gradient near the center (Müller et al. 2003).
+    Poly6,
+    /// `(h - r)³`, keeping a strong gradient near the center to prevent clustering (Müller et al. 2003).
+    Spiky,
+    /// `(h - r)²`, a softer spiky kernel.
+    SpikyQuadratic,
+    /// Cubic B-spline (Monaghan 1992).
+    CubicSpline,
+    /// Wendland C2, free of the pairing instability (Dehnen & Aly 2012).
+    WendlandC2,
+    /// `h - r`, the shape of the Laplacian of the viscosity kernel of Müller et al. (2003),
+    /// which weighs the velocity differences as a Laplacian based viscosity would.
+    Viscosity,
+}
+
+impl Kernel {
+    /// Value of the kernel at `distance` for a support radius `radius`.
+    pub fn value(&self, distance: f32, radius: f32) -> f32 {
+        if distance >= radius {
+            return 0.0;
+        }
+
+        let h = radius;
+        let q = distance / h;
+
+        match self {
+            Kernel::Poly6 => {
+                let value = h * h - distance * distance;
+                4.0 / (PI * h.powi(8)) * value * value * value
+            },
+            Kernel::Spiky => 10.0 / (PI * h.powi(5)) * (h - distance).powi(3),
+            Kernel::SpikyQuadratic => 6.0 / (PI * h.powi(4)) * (h - distance) * (h - distance),
+            Kernel::CubicSpline => {
+                let sigma = 40.0 / (7.0 * PI * h * h);
+                if q <= 0.5 {
+                    sigma * (6.0 * (q * q * q - q * q) + 1.0)
+                } else {
+                    sigma * 2.0 * (1.0 - q).powi(3)
+                }
+            },
+            Kernel::WendlandC2 => 7.0 / (PI * h * h) * (1.0 - q).powi(4) * (1.0 + 4.0 * q),
+            Kernel::Viscosity => 3.0 / (PI * h.powi(3)) * (h - distance),
+        }
+    }
+
+    /// Derivative of the kernel with respect to the distance, negative inside the support.
+    pub fn derivative(&self, distance: f32, radius: f32) -> f32 {
+        if distance >= radius {
+            return 0.0;
+        }
+
+        let h = radius;
+        let q = distance / h;
+
+        match self {
+            Kernel::Poly6 => {
+                let value = h * h - distance * distance;
+                -24.0 / (PI * h.powi(8)) * distance * value * value
+            },
+            Kernel::Spiky => -30.0 / (PI * h.powi(5)) * (h - distance) * (h - distance),
+            Kernel::SpikyQuadratic => -12.0 / (PI * h.powi(4)) * (h - distance),
+            Kernel::CubicSpline => {
+                let sigma = 40.0 / (7.0 * PI * h * h);
+                if q <= 0.5 {
+                    sigma * 6.0 * (3.0 * q * q - 2.0 * q) / h
+                } else {
+                    -sigma * 6.0 * (1.0 - q) * (1.0 - q) / h
+                }
+            },
+            Kernel::WendlandC2 => -140.0 / (PI * h * h * h) * q * (1.0 - q).powi(3),
+            Kernel::Viscosity => -3.0 / (PI * h.powi(3)),
+        }
+    }
+}
+
+/// The kernel used for each purpose of the SPH solvers.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct KernelSettings {
+    /// Density estimation, also used to sample the boundaries and the surface.
+    pub density: Kernel,
+    /// Pressure forces and every other gradient.
+    pub pressure: Kernel,
+    /// Weight of the velocity differences in the viscosity force.
+    pub viscosity: Kernel,
+}
+
+impl Default for KernelSettings {
+    fn default() -> Self {
+        Self {
+            density: Kernel::SpikyQuadratic,
+            pressure: Kernel::SpikyQuadratic,
+            viscosity: Kernel::Poly6,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KERNELS: [Kernel; 6] = [Kernel::Poly6, Kernel::Spiky, Kernel::SpikyQuadratic, Kernel::CubicSpline, Kernel::WendlandC2, Kernel::Viscosity];
+
+    /// Integrates the kernel over the disc of radius `radius` with the midpoint rule in polar coordinates.
+    fn integrate(kernel: Kernel, radius: f32) -> f64 {
+        let steps = 20_000;
+        let dr = radius as f64 / steps as f64;
+        (0..steps).map(|k| {
+            let r = (k as f64 + 0.5) * dr;
+            kernel.value(r as f32, radius) as f64 * 2.0 * std::f64::consts::PI * r * dr
+        }).sum()
+    }
+
+    #[test]
+    fn kernels_integrate_to_one() {
+        for kernel in KERNELS {
+            for radius in [0.5, 1.0, 30.0] {
+                let integral = integrate(kernel, radius);
+                assert!((integral - 1.0).abs() < 1e-3, "{:?} with radius {} integrates to {}", kernel, radius, integral);
+            }
+        }
+    }
+
+    #[test]
+    fn kernels_vanish_outside_their_support() {
+        for kernel in KERNELS {
+            assert_eq!(kernel.value(30.0, 30.0), 0.0, "{:?}", kernel);
+            assert_eq!(kernel.value(45.0, 30.0), 0.0, "{:?}", kernel);
+            assert_eq!(kernel.derivative(45.0, 30.0), 0.0, "{:?}", kernel);
+        }
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let radius = 30.0;
+        let step = 1e-2;
+
+        for kernel in KERNELS {
+            for distance in [1.0, 7.5, 14.0, 16.0, 25.0, 29.0] {
+                let finite_difference = (kernel.value(distance + step, radius) - kernel.value(distance - step, radius)) / (2.0 * step);
+                let derivative = kernel.derivative(distance, radius);
+                let tolerance = 1e-3 * derivative.abs().max(kernel.value(0.0, radius) / radius);
+                assert!((finite_difference - derivative).abs() < tolerance, "{:?} at {}: {} != {}", kernel, distance, derivative, finite_difference);
+            }
+        }
+    }
+
+    #[test]
+    fn kernels_decrease_with_distance() {
+        for kernel in KERNELS {
+            let values: Vec<f32> = (0..30).map(|distance| kernel.value(distance as f32, 30.0)).collect();
+            assert!(values.windows(2).all(|pair| pair[1] <= pair[0]), "{:?}", kernel);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use crate::core::sciences::maths::Vector2;
+
+use super::obstacle::ObstacleShape;
+
+/// Number of candidates tried around each active sample by the Poisson-disk sampling.
+const POISSON_CANDIDATES: usize = 30;
+
+/// Initial arrangement of fluid particles, generated by the backend from a few parameters.
+///
+/// Serialized with an internal `type` tag, e.g.
+/// `{ "type": "droplet", "center": { "x": 400, "y": 200 }, "radius": 80, "spacing": 8 }`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FluidLayout {
+    /// Grid filling the rectangle from `min` to `max`, each particle moved randomly by up to `jitter` times the spacing.
+    Block { min: Vector2, max: Vector2, spacing: f32, #[serde(default)] jitter: f32 },
+    /// Column of liquid resting against the left wall, `width` and `height` being fractions of the box.
+    DamBreak { width: f32, height: f32, spacing: f32 },
+    /// Disc of particles on a grid.
+    Droplet { center: Vector2, radius: f32, spacing: f32 },
+    /// `count` particles placed uniformly at random in the rectangle from `min` to `max`.
+    RandomFill { min: Vector2, max: Vector2, count: usize },
+    /// Poisson-disk sampling of a polygon (Bridson 2007), no two particles closer than `min_distance`.
+    PoissonDisk { vertices: Vec<Vector2>, min_distance: f32 },
+}
+
+impl FluidLayout {
+    /// Generates the particle positions of the layout in a box of size `box_size`.
+    ///
+    /// Fails before allocating when the layout would hold more than `max_particles` particles.
+    pub fn generate(&self, box_size: Vector2, max_particles: usize) -> Result<Vec<Vector2>, String> {
+        match self {
+            FluidLayout::Block { min, max, spacing, jitter } => generate_block(*min, *max, *spacing, *jitter, max_particles),
+            FluidLayout::DamBreak { width, height, spacing } => {
+                let min = Vector2::new(0.0, box_size.y * (1.0 - height.clamp(0.0, 1.0)));
+                let max = Vector2::new(box_size.x * width.clamp(0.0, 1.0), box_size.y);
+                generate_block(min, max, *spacing, 0.0, max_particles)
+            },
+            FluidLayout::Droplet { center, radius, spacing } => {
+                let offset = Vector2::new(*radius, *radius);
+                // The disc covers about pi/4 of its bounding square, so a square of twice the budget holds any disc that fits
+                check_spacing(*spacing)?;
+                let positions = generate_block(*center - offset, *center + offset, *spacing, 0.0, max_particles.saturating_mul(2))
+                    .map_err(|_| format!("The droplet has more particles than the {} left", max_particles))?;
+                let positions: Vec<Vector2> = positions.into_iter().filter(|position| position.distance_to(*center) <= *radius).collect();
+                check_count(positions.len(), max_particles)?;
+                Ok(positions)
+            },
+            FluidLayout::RandomFill { min, max, count } => {
+                check_count(*count, max_particles)?;
+                let mut rng = rand::thread_rng();
+                Ok((0..*count).map(|_| Vector2::new(
+                    min.x + (max.x - min.x) * rng.gen::<f32>(),
+                    min.y + (max.y - min.y) * rng.gen::<f32>()
+                )).collect())
+            },
+            FluidLayout::PoissonDisk { vertices, min_distance } => generate_poisson_disk(vertices, *min_distance, max_particles),
+        }
+    }
+}
+
+fn check_spacing(spacing: f32) -> Result<(), String> {
+    if spacing <= 0.0 || !spacing.is_finite() {
+        return Err(format!("The spacing of a layout must be positive, got {}", spacing));
+    }
+
+    Ok(())
+}
+
+fn check_count(count: usize, max_particles: usize) -> Result<(), String> {
+    if count > max_particles {
+        return Err(format!("The layout has {} particles, more than the {} left", count, max_particles));
+    }
+
+    Ok(())
+}
+
+/// Fills the rectangle from `min` to `max` with a grid of particles centered in their cells.
+fn generate_block(min: Vector2, max: Vector2, spacing: f32, jitter: f32, max_particles: usize) -> Result<Vec<Vector2>, String> {
+    check_spacing(spacing)?;
+
+    // Float to integer casts saturate, so a huge or infinite rectangle ends up rejected below
+    let columns = ((max.x - min.x) / spacing).floor().max(0.0) as usize;
+    let rows = ((max.y - min.y) / spacing).floor().max(0.0) as usize;
+    let count = match columns.checked_mul(rows) {
+        Some(count) => count,
+        None => return Err(format!("The layout has {} by {} particles, more than the {} left", columns, rows, max_particles)),
+    };
+    check_count(count, max_particles)?;
+
+    let mut rng = rand::thread_rng();
+    let mut positions = Vec::with_capacity(count);
+
+    for row in 0..rows {
+        for column in 0..columns {
+            let position = min + Vector2::new(column as f32 + 0.5, row as f32 + 0.5) * spacing;
+            let displacement = Vector2::new(rng.gen_range(-0.5..=0.5), rng.gen_range(-0.5..=0.5)) * jitter * spacing;
+            positions.push(position + displacement);
+        }
+    }
+
+    Ok(positions)
+}
+
+/// Fills a polygon by Poisson-disk sampling, growing the samples from a random point inside it.
+fn generate_poisson_disk(vertices: &[Vector2], min_distance: f32, max_particles: usize) -> Result<Vec<Vector2>, String> {
+    check_spacing(min_distance)?;
+    if vertices.len() < 3 {
+        return Err("A Poisson-disk layout needs a polygon of at least 3 vertices".to_string());
+    }
+    if vertices.iter().any(|vertex| !vertex.x.is_finite() || !vertex.y.is_finite()) {
+        return Err("The polygon of a Poisson-disk layout must have finite vertices".to_string());
+    }
+
+    let polygon = ObstacleShape::Polygon { vertices: vertices.to_vec() };
+    let is_inside = |point: Vector2| polygon.signed_distance(point).0 < 0.0;
+
+    let min = vertices.iter().fold(Vector2::new(f32::MAX, f32::MAX), |min, vertex| Vector2::new(min.x.min(vertex.x), min.y.min(vertex.y)));
+    let max = vertices.iter().fold(Vector2::new(f32::MIN, f32::MIN), |max, vertex| Vector2::new(max.x.max(vertex.x), max.y.max(vertex.y)));
+
+    // Background grid holding at most one sample per cell, only storing the occupied cells
+    // so that its size follows the number of samples rather than the bounding box
+    let cell_size = min_distance / 2.0_f32.sqrt();
+    let columns = (((max.x - min.x) / cell_size).ceil() as usize).saturating_add(1);
+    let rows = (((max.y - min.y) / cell_size).ceil() as usize).saturating_add(1);
+    let mut grid: HashMap<(usize, usize), usize> = HashMap::new();
+    let cell_of = |point: Vector2| (((point.x - min.x) / cell_size) as usize, ((point.y - min.y) / cell_size) as usize);
+
+    let mut rng = rand::thread_rng();
+    let first = match (0..1000).map(|_| Vector2::new(rng.gen_range(min.x..=max.x), rng.gen_range(min.y..=max.y))).find(|point| is_inside(*point)) {
+        Some(point) => point,
+        None => return Err("No point was found inside the polygon of the Poisson-disk layout".to_string()),
+    };
+
+    let mut samples = vec![first];
+    let mut active = vec![0];
+    let (x, y) = cell_of(first);
+    grid.insert((x, y), 0);
+
+    while !active.is_empty() {
+        let active_index = rng.gen_range(0..active.len());
+        let center = samples[active[active_index]];
+        let mut found = false;
+
+        for _ in 0..POISSON_CANDIDATES {
+            let angle = rng.gen_range(0.0..2.0 * PI);
+            let distance = rng.gen_range(min_distance..2.0 * min_distance);
+            let candidate = center + Vector2::new(angle.cos(), angle.sin()) * distance;
+
+            if candidate.x < min.x || candidate.y < min.y || candidate.x > max.x || candidate.y > max.y || !is_inside(candidate) {
+                continue;
+            }
+
+            // Every sample closer than `min_distance` lies in the 5x5 cells around the candidate
+            let (cx, cy) = cell_of(candidate);
+            let too_close = (cy.saturating_sub(2)..cy.saturating_add(3).min(rows)).any(|y| {
+                (cx.saturating_sub(2)..cx.saturating_add(3).min(columns)).any(|x| {
+                    grid.get(&(x, y)).map_or(false, |sample| samples[*sample].distance_to(candidate) < min_distance)
+                })
+            });
+
+            if !too_close {
+                check_count(samples.len() + 1, max_particles)?;
+                grid.insert((cx, cy), samples.len());
+                active.push(samples.len());
+                samples.push(candidate);
+                found = true;
+                break;
+            }
+        }
+
+        if !found {
+            active.swap_remove(active_index);
+        }
+    }
+
+    Ok(samples)
+}
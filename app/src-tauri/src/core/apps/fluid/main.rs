@@ -1,10 +1,24 @@
-use std::{cell, f32::consts::PI, mem::discriminant};
+use std::{cell, collections::HashMap, f32::consts::PI, mem::discriminant};
 
-use serde::Serialize;
+use serde::{Serialize, Deserialize};
 
 use colorgrad::Gradient;
 
 use crate::core::sciences::maths::Vector2;
+use crate::core::sciences::maths::integrators::Integrator;
+use crate::core::sciences::physics::force_fields::{ForceField, total_acceleration};
+
+use super::obstacle::Obstacle;
+use super::boundary::BoundaryParticles;
+use super::emitter::{Emitter, Sink};
+use super::moving_boundary::MovingBoundary;
+use super::thermal::ThermalRegion;
+use super::visualization::{VisualFilter, ColorRange};
+use super::surface::FluidSurface;
+use super::kernel::KernelSettings;
+
+/// Rate, per second, at which the pointer relaxes the velocities at its center toward its target velocity.
+const INTERACTION_RATE: f32 = 100.0;
 
 const CELL_OFFSETS: [Vector2; 9] = [
     Vector2 { x: -1.0, y: -1.0 },
@@ -18,6 +32,13 @@ const CELL_OFFSETS: [Vector2; 9] = [
     Vector2 { x: 1.0, y: 1.0 }
 ];
 
+/// Axes along which the fluid domain wraps around, a particle leaving through one wall entering through the opposite one.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+pub struct PeriodicBoundaries {
+    pub x: bool,
+    pub y: bool,
+}
+
 #[derive(Serialize, PartialEq, Clone, Debug)]
 pub struct FluidParticles {
     // ALL PARTICLES PROPERTIES
@@ -26,12 +47,19 @@ pub struct FluidParticles {
     pub target_density: f32, 
     pub pressure_multiplier: f32,
     pub smoothing_radius: f32,
+    pub ambient_temperature: f32,
+    pub periodic: PeriodicBoundaries,
+    /// Size of the domain, needed to wrap the periodic axes.
+    pub domain_size: Vector2,
     // EACH PARTICLE PROPERTIES
     pub positions: Vec<Vector2>,
     pub predicted_positions: Vec<Vector2>,
     pub velocities: Vec<Vector2>,
     pub densities: Vec<f32>,
     pub colors: Vec<String>, // store in hex string format and not in colorgrad::Color to allow serialization
+    pub phases: Vec<u8>,
+    pub pressures: Vec<f32>,
+    pub temperatures: Vec<f32>,
     pub spatial_lookup: Vec<(usize, usize)>,
     pub lookup_start: Vec<usize>,
 }
@@ -45,12 +73,18 @@ impl FluidParticles {
             target_density,
             pressure_multiplier,
             smoothing_radius,
+            ambient_temperature: 20.0,
+            periodic: PeriodicBoundaries::default(),
+            domain_size: Vector2::new(800.0, 600.0),
             // EACH PARTICLE PROPERTIES
             positions: Vec::new(),
             predicted_positions: Vec::new(),
             velocities: Vec::new(),
             densities: Vec::new(),
             colors: Vec::new(),
+            phases: Vec::new(),
+            pressures: Vec::new(),
+            temperatures: Vec::new(),
             spatial_lookup: Vec::new(),
             lookup_start: Vec::new(),
         }
@@ -67,19 +101,85 @@ impl FluidParticles {
     }
 
     pub fn push(&mut self, position: Vector2) -> () {
+        self.spawn(position, Vector2::zero(), 0);
+    }
+
+    pub fn spawn(&mut self, position: Vector2, velocity: Vector2, phase: u8) -> () {
         self.positions.push(position);
         self.predicted_positions.push(position);
-        self.velocities.push(Vector2::zero());
+        self.velocities.push(velocity);
         self.densities.push(0.0);
         self.colors.push("#FFFFFFFF".to_string());
+        self.phases.push(phase);
+        self.pressures.push(0.0);
+        self.temperatures.push(self.ambient_temperature);
         self.spatial_lookup.push((0, 0));
         self.lookup_start.push(0);
     }
 
+    /// Removes the particle `i` by swapping it with the last one, in every per-particle array.
+    /// The spatial lookup is shrunk as well and must be updated before the next neighbour search.
+    pub fn swap_remove(&mut self, i: usize) -> () {
+        self.positions.swap_remove(i);
+        self.predicted_positions.swap_remove(i);
+        self.velocities.swap_remove(i);
+        self.densities.swap_remove(i);
+        self.colors.swap_remove(i);
+        self.phases.swap_remove(i);
+        self.pressures.swap_remove(i);
+        self.temperatures.swap_remove(i);
+        self.spatial_lookup.pop();
+        self.lookup_start.pop();
+    }
+
+    /// Number of cells tiling a periodic axis of length `size`, the cells being stretched
+    /// to be at least as large as the smoothing radius.
+    fn periodic_cell_count(&self, size: f32) -> f32 {
+        (size / self.smoothing_radius).floor().max(1.0)
+    }
+
     pub fn get_cell_position(&self, position: &Vector2) -> Vector2 {
-        let x = (position.x / self.smoothing_radius).floor();
-        let y = (position.y / self.smoothing_radius).floor();
-        Vector2::new(x, y)
+        let cell_coordinate = |coordinate: f32, periodic: bool, size: f32| {
+            if periodic {
+                let cells = self.periodic_cell_count(size);
+                (coordinate.rem_euclid(size) / size * cells).floor().min(cells - 1.0)
+            } else {
+                (coordinate / self.smoothing_radius).floor()
+            }
+        };
+
+        Vector2::new(
+            cell_coordinate(position.x, self.periodic.x, self.domain_size.x),
+            cell_coordinate(position.y, self.periodic.y, self.domain_size.y)
+        )
+    }
+
+    /// Wraps a cell position around the periodic axes.
+    pub fn wrap_cell_position(&self, cell_position: Vector2) -> Vector2 {
+        let mut wrapped = cell_position;
+        if self.periodic.x {
+            wrapped.x = cell_position.x.rem_euclid(self.periodic_cell_count(self.domain_size.x));
+        }
+        if self.periodic.y {
+            wrapped.y = cell_position.y.rem_euclid(self.periodic_cell_count(self.domain_size.y));
+        }
+        wrapped
+    }
+
+    /// Returns the offset from `b` to `a`, crossing the periodic boundaries when it is shorter (minimum image convention).
+    pub fn offset(&self, a: Vector2, b: Vector2) -> Vector2 {
+        let mut offset = a - b;
+        if self.periodic.x {
+            offset.x -= self.domain_size.x * (offset.x / self.domain_size.x).round();
+        }
+        if self.periodic.y {
+            offset.y -= self.domain_size.y * (offset.y / self.domain_size.y).round();
+        }
+        offset
+    }
+
+    pub fn distance(&self, a: Vector2, b: Vector2) -> f32 {
+        self.offset(a, b).magnitude()
     }
 
     pub fn get_cell_hash(&self, cell_position: &Vector2) -> usize {
@@ -94,7 +194,7 @@ impl FluidParticles {
     }
 
     pub fn update_spatial_lookup(&mut self) {
-        for (i, position) in self.positions.iter().enumerate() {
+        for (i, position) in self.predicted_positions.iter().enumerate() {
             // get_cell_position
             let cell_position = self.get_cell_position(position);
 
@@ -119,22 +219,85 @@ impl FluidParticles {
     }
 }
 
+/// The pressure solver used by `Fluid::update`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FluidSolver {
+    /// Weakly compressible SPH, with an explicit pressure force from the equation of state.
+    ExplicitSph,
+    /// Position Based Fluids (Macklin & Müller 2013), solving density constraints iteratively.
+    PositionBased,
+    /// Implicit Incompressible SPH (Ihmsen et al. 2014), solving the pressures up to `max_density_error`.
+    Implicit,
+}
+
+/// Convergence of the last solve of the iterative solvers, reported to the frontend.
+#[derive(Serialize, Clone, Copy, Default, PartialEq, Debug)]
+pub struct SolverMetrics {
+    /// Number of iterations done, 0 for the explicit solver.
+    pub iterations: usize,
+    /// Average density error relative to the rest density after the last iteration.
+    pub density_error: f32,
+}
+
 pub struct Fluid {
     // FLUID PROPERTIES
     pub particles: FluidParticles,
+    pub solver: FluidSolver,
+    pub solver_iterations: usize,
+    pub kernels: KernelSettings,
+    pub max_density_error: f32,
+    pub metrics: SolverMetrics,
+    pub artificial_pressure_strength: f32,
+    pub cfl_factor: f32,
+    pub max_substeps: usize,
+    pub substeps: usize,
+    pub max_acceleration: f32,
     pub gravity: f32,
-    pub visual_filter: u8,
+    /// Scheme moving the particles under the gravity and the force fields, the solver adding the
+    /// velocity change of the pressure, viscosity and other internal forces on top of it.
+    pub integrator: Integrator,
+    /// Position and velocity given by `integrator` for each particle during the current step.
+    external_motion: Vec<(Vector2, Vector2)>,
+    /// External force fields, added to the gravity.
+    pub force_fields: Vec<ForceField>,
+    pub visual_filter: VisualFilter,
+    pub color_range: ColorRange,
+    pub displayed_range: (f32, f32),
+    pub show_surface: bool,
+    pub surface_resolution: f32,
+    pub surface_iso_level: f32,
+    pub surface: Option<FluidSurface>,
     pub collision_restitution: f32,
     pub viscosity_strength: f32,
     pub interactive_force: bool,
     pub interactive_force_position: Vector2,
     pub interactive_force_mode: bool,
+    pub surface_tension_strength: f32,
+    pub adhesion_strength: f32,
+    pub thermal_diffusivity: f32,
+    pub thermal_expansion: f32,
+    pub viscoelastic: bool,
+    pub spring_stiffness: f32,
+    pub yield_ratio: f32,
+    pub plasticity: f32,
+    /// Rest lengths of the viscoelastic springs, keyed by the particle indices in increasing order.
+    pub springs: HashMap<(usize, usize), f32>,
     // BOUNDARY PROPERTIES
     pub box_bound_x: f32,
     pub box_bound_y: f32,
+    pub obstacles: Vec<Obstacle>,
+    pub use_boundary_particles: bool,
+    pub boundary_particles: BoundaryParticles,
+    pub emitters: Vec<Emitter>,
+    pub sinks: Vec<Sink>,
+    pub max_particles: usize,
+    pub moving_boundaries: Vec<MovingBoundary>,
+    pub thermal_regions: Vec<ThermalRegion>,
     // OTHER PROPERTIES
     pub velocity_gradient: Gradient,
-    pub density_gradient: Gradient
+    pub density_gradient: Gradient,
+    pub temperature_gradient: Gradient
 }
 
 impl Fluid {
@@ -144,44 +307,93 @@ impl Fluid {
         Fluid {
             // FLUID PROPERTIES
             particles,
+            solver: FluidSolver::ExplicitSph,
+            solver_iterations: 4,
+            kernels: KernelSettings::default(),
+            max_density_error: 0.01,
+            metrics: SolverMetrics::default(),
+            artificial_pressure_strength: 0.1,
+            cfl_factor: 0.4,
+            max_substeps: 16,
+            substeps: 1,
+            max_acceleration: 0.0,
             gravity: 0.0,
-            visual_filter: 0,
+            integrator: Integrator::default(),
+            external_motion: Vec::new(),
+            force_fields: Vec::new(),
+            visual_filter: VisualFilter::None,
+            color_range: ColorRange::Auto,
+            displayed_range: (0.0, 1.0),
+            show_surface: false,
+            surface_resolution: 6.0,
+            surface_iso_level: 0.5,
+            surface: None,
             collision_restitution: 0.95,
             viscosity_strength: 0.1,
             interactive_force: false,
             interactive_force_position: Vector2::zero(),
             interactive_force_mode: true,
+            surface_tension_strength: 0.0,
+            adhesion_strength: 0.0,
+            thermal_diffusivity: 0.0,
+            thermal_expansion: 0.0,
+            viscoelastic: false,
+            spring_stiffness: 1000.0,
+            yield_ratio: 0.1,
+            plasticity: 0.3,
+            springs: HashMap::new(),
             // BOUNDARY PROPERTIES
             box_bound_x: 800.0,
             box_bound_y: 600.0,
+            obstacles: Vec::new(),
+            use_boundary_particles: false,
+            boundary_particles: BoundaryParticles::new(),
+            emitters: Vec::new(),
+            sinks: Vec::new(),
+            max_particles: 5000,
+            moving_boundaries: Vec::new(),
+            thermal_regions: Vec::new(),
             // OTHER PROPERTIES
             velocity_gradient,
-            density_gradient: colorgrad::CustomGradient::new().html_colors(&["#0077ff", "#ffffff", "ff3131"]).domain(&[0.0, 0.5, 1.0]).build().unwrap()
+            density_gradient: colorgrad::CustomGradient::new().html_colors(&["#0077ff", "#ffffff", "ff3131"]).domain(&[0.0, 0.5, 1.0]).build().unwrap(),
+            temperature_gradient: colorgrad::CustomGradient::new().html_colors(&["#2b2dff", "#b03bd1", "#ff7b00", "#ffe14d"]).domain(&[0.0, 0.35, 0.7, 1.0]).build().unwrap()
         }
     }
 
-    fn smoothing_kernel(&self, distance: f32) -> f32 {
-        if distance >= self.particles.smoothing_radius {
-            return 0.0;
-        }
-
-        let volume = std::f32::consts::PI * self.particles.smoothing_radius.powf(4.0) / 6.0;
-        //let value = f32::max(0.0, self.particles.smoothing_radius * self.particles.smoothing_radius - distance * distance);
-
-        (self.particles.smoothing_radius - distance) * (self.particles.smoothing_radius - distance) / volume
+    pub(super) fn smoothing_kernel(&self, distance: f32) -> f32 {
+        self.kernels.density.value(distance, self.particles.smoothing_radius)
     }
 
-    fn calculate_density(&self, i: usize) -> f32 {
+    pub(super) fn calculate_density(&self, i: usize) -> f32 {
         let mut density = self.smoothing_kernel(0.0); // au moins la densité de la particule i
 
 
         // OPTIMIZATION: only search for neighbors in the cells grid
         for other_i in self.in_radius_neighbors_search(i) {
-            let distance = self.particles.predicted_positions[i].distance_to(self.particles.predicted_positions[other_i]);
+            let distance = self.particles.distance(self.particles.predicted_positions[i], self.particles.predicted_positions[other_i]);
             let influence = self.smoothing_kernel(distance);
             density += self.particles.mass * influence;
         }
 
+        // Boundary particles act as fluid particles at rest density
+        if self.use_boundary_particles {
+            let position = self.particles.predicted_positions[i];
+            for b in self.boundary_particles.neighbors(position, self.particles.smoothing_radius) {
+                let distance = position.distance_to(self.boundary_particles.positions[b]);
+                density += self.particles.target_density * self.boundary_particles.volumes[b] * self.smoothing_kernel(distance);
+            }
+        }
+
+        // Moving boundaries are always sampled with boundary particles
+        for moving_boundary in self.moving_boundaries.iter() {
+            let position = self.particles.predicted_positions[i];
+            let boundary_particles = &moving_boundary.boundary_particles;
+            for b in boundary_particles.neighbors(position, self.particles.smoothing_radius) {
+                let distance = position.distance_to(boundary_particles.positions[b]);
+                density += self.particles.target_density * boundary_particles.volumes[b] * self.smoothing_kernel(distance);
+            }
+        }
+
         // NO OPTIMIZATION: search for all neighbors
         //for other_i in 0..self.particles.len() {
         //    let distance = self.particles.predicted_positions[i].distance_to(self.particles.predicted_positions[other_i]);
@@ -192,25 +404,26 @@ impl Fluid {
         density
     }
 
-    fn smoothing_kernel_derivative(&self, distance: f32) -> f32 {
-        if distance >= self.particles.smoothing_radius {
-            return 0.0;
-        }
-
-        //let offset = self.particles.smoothing_radius * self.particles.smoothing_radius - distance * distance;
-        let scale = 12.0 / (std::f32::consts::PI * self.particles.smoothing_radius.powf(4.0));
-
-        (distance - self.particles.smoothing_radius) * scale
+    pub(super) fn smoothing_kernel_derivative(&self, distance: f32) -> f32 {
+        self.kernels.pressure.derivative(distance, self.particles.smoothing_radius)
     }
 
-    fn in_radius_neighbors_search(&self, i: usize) -> Vec<usize> {
+    pub(super) fn in_radius_neighbors_search(&self, i: usize) -> Vec<usize> {
         let mut neighbors = Vec::new();
         
         let cell_position = self.particles.get_cell_position(&self.particles.predicted_positions[i]);
         let sqr_radius = self.particles.smoothing_radius * self.particles.smoothing_radius;
+        let mut visited_cells: Vec<Vector2> = Vec::with_capacity(CELL_OFFSETS.len());
 
         for offset in CELL_OFFSETS.iter() {
-            let neighbor_position = cell_position + *offset;
+            let neighbor_position = self.particles.wrap_cell_position(cell_position + *offset);
+
+            // A periodic axis shorter than three cells wraps several offsets onto the same cell
+            if visited_cells.contains(&neighbor_position) {
+                continue;
+            }
+            visited_cells.push(neighbor_position);
+
             let neighbor_hash = self.particles.get_cell_hash(&neighbor_position);
             let neighbor_key = self.particles.get_cell_key_from_hash(neighbor_hash);
 
@@ -228,7 +441,7 @@ impl Fluid {
                     continue;
                 }             
 
-                let sqr_distance = self.particles.predicted_positions[i].distance_to_squared(self.particles.predicted_positions[*other_i]);
+                let sqr_distance = self.particles.offset(self.particles.predicted_positions[i], self.particles.predicted_positions[*other_i]).magnitude_squared();
 
                 if sqr_distance < sqr_radius {
                     neighbors.push(*other_i);
@@ -239,23 +452,16 @@ impl Fluid {
         neighbors
     }
 
-    fn viscosity_kernel(&self, distance: f32) -> f32 {
-        if distance >= self.particles.smoothing_radius {
-            return 0.0;
-        }
-
-        let volume = PI * self.particles.smoothing_radius.powf(8.0) / 4.0;
-        let value = self.particles.smoothing_radius * self.particles.smoothing_radius - distance * distance;
-
-        value * value * value / volume
+    pub(super) fn viscosity_kernel(&self, distance: f32) -> f32 {
+        self.kernels.viscosity.value(distance, self.particles.smoothing_radius)
     }
 
-    fn calculate_viscosity_force(&self, i: usize) -> Vector2 {
+    pub(super) fn calculate_viscosity_force(&self, i: usize) -> Vector2 {
         let mut viscosity_force = Vector2::zero();
 
         // OPTIMIZATION: only search for neighbors in the cells grid
         for other_i in self.in_radius_neighbors_search(i) {
-            let distance = self.particles.predicted_positions[i].distance_to(self.particles.predicted_positions[other_i]);
+            let distance = self.particles.distance(self.particles.predicted_positions[i], self.particles.predicted_positions[other_i]);
             let influecne = self.viscosity_kernel(distance);
             let velocity_difference = self.particles.velocities[other_i] - self.particles.velocities[i];
             viscosity_force += velocity_difference * influecne;
@@ -285,7 +491,7 @@ impl Fluid {
 
         // OPTIMIZATION: only search for neighbors in the cells grid
         for other_i in self.in_radius_neighbors_search(i) {
-            let offset = self.particles.predicted_positions[i] - self.particles.predicted_positions[other_i];
+            let offset = self.particles.offset(self.particles.predicted_positions[i], self.particles.predicted_positions[other_i]);
             let distance = offset.magnitude();
             let direction = if distance == 0.0 { Vector2::random() } else { offset / distance };
             let slope = self.smoothing_kernel_derivative(distance);
@@ -295,6 +501,21 @@ impl Fluid {
             pressure_force += direction * shared_pressure * slope * self.particles.mass / density;
         }
 
+        // Boundary particles mirror the pressure of the fluid particle
+        if self.use_boundary_particles {
+            let position = self.particles.predicted_positions[i];
+            let pressure = self.particles.calculate_pressure(self.particles.densities[i]);
+            for b in self.boundary_particles.neighbors(position, self.particles.smoothing_radius) {
+                let offset = position - self.boundary_particles.positions[b];
+                let distance = offset.magnitude();
+                if distance <= f32::EPSILON {
+                    continue;
+                }
+                let slope = self.smoothing_kernel_derivative(distance);
+                pressure_force += offset / distance * pressure * slope * self.boundary_particles.volumes[b];
+            }
+        }
+
         // NO OPTIMIZATION: search for all neighbors
         //for other_i in 0..self.particles.len() {
         //    let offset = self.particles.predicted_positions[i] - self.particles.predicted_positions[other_i];
@@ -310,26 +531,280 @@ impl Fluid {
         pressure_force
     }
 
-    fn calculate_interaction_force(&self, position: Vector2, radius: f32, strength: f32, i: usize) -> Vector2 {
-        let mut interaction_force = Vector2::zero();
+    /// Cohesion kernel of Akinci et al. (2013), attractive at long range and
+    /// slightly repulsive at short range to avoid clustering.
+    fn cohesion_kernel(&self, distance: f32) -> f32 {
+        let h = self.particles.smoothing_radius;
+
+        if distance <= 0.0 || distance > h {
+            return 0.0;
+        }
+
+        let scale = 32.0 / (PI * h.powf(8.0));
+        let value = (h - distance).powi(3) * distance.powi(3);
+
+        if 2.0 * distance > h {
+            value * scale
+        } else {
+            (2.0 * value - h.powi(6) / 64.0) * scale
+        }
+    }
+
+    /// Adhesion kernel of Akinci et al. (2013), only non-zero in the outer half of the smoothing radius.
+    fn adhesion_kernel(&self, distance: f32) -> f32 {
+        let h = self.particles.smoothing_radius;
+
+        if 2.0 * distance <= h || distance > h {
+            return 0.0;
+        }
+
+        let value = -4.0 * distance * distance / h + 6.0 * distance - 2.0 * h;
+
+        0.007 / h.powf(2.25) * value.max(0.0).powf(0.25)
+    }
+
+    /// Computes the surface normal of particle `i` (the scaled gradient of the colour field),
+    /// pointing out of the fluid, large at the free surface and close to zero inside the fluid.
+    fn calculate_surface_normal(&self, i: usize) -> Vector2 {
+        let mut normal = Vector2::zero();
+
+        for other_i in self.in_radius_neighbors_search(i) {
+            let offset = self.particles.offset(self.particles.predicted_positions[i], self.particles.predicted_positions[other_i]);
+            let distance = offset.magnitude();
+
+            if distance <= f32::EPSILON {
+                continue;
+            }
+
+            let slope = self.smoothing_kernel_derivative(distance);
+            normal -= offset / distance * slope * self.particles.mass / self.particles.densities[other_i];
+        }
+
+        normal * self.particles.smoothing_radius
+    }
+
+    /// Computes the surface tension acceleration of particle `i` from the cohesion between
+    /// neighbours and the curvature correction given by the difference of their surface normals.
+    fn calculate_surface_tension_force(&self, i: usize, normals: &[Vector2]) -> Vector2 {
+        let mut surface_tension_force = Vector2::zero();
+
+        for other_i in self.in_radius_neighbors_search(i) {
+            let offset = self.particles.offset(self.particles.predicted_positions[i], self.particles.predicted_positions[other_i]);
+            let distance = offset.magnitude();
+
+            if distance <= f32::EPSILON {
+                continue;
+            }
+
+            let cohesion = offset / distance * self.particles.mass * self.cohesion_kernel(distance);
+            let curvature = normals[i] - normals[other_i];
+            let correction = 2.0 * self.particles.target_density / (self.particles.densities[i] + self.particles.densities[other_i]);
+
+            surface_tension_force -= (cohesion + curvature) * correction;
+        }
+
+        surface_tension_force * self.surface_tension_strength
+    }
+
+    /// Returns the closest point of every boundary lying within the smoothing radius of `position`.
+    fn closest_boundary_points(&self, position: Vector2) -> Vec<Vector2> {
+        let mut points = Vec::new();
+        let h = self.particles.smoothing_radius;
+
+        // The periodic axes have no walls
+        if !self.particles.periodic.x {
+            if position.x < h {
+                points.push(Vector2::new(0.0, position.y));
+            }
+            if self.box_bound_x - position.x < h {
+                points.push(Vector2::new(self.box_bound_x, position.y));
+            }
+        }
+        if !self.particles.periodic.y {
+            if position.y < h {
+                points.push(Vector2::new(position.x, 0.0));
+            }
+            if self.box_bound_y - position.y < h {
+                points.push(Vector2::new(position.x, self.box_bound_y));
+            }
+        }
+
+        for obstacle in self.obstacles.iter() {
+            let (distance, normal) = obstacle.signed_distance(position);
+            if distance > 0.0 && distance < h {
+                points.push(position - normal * distance);
+            }
+        }
+
+        for moving_boundary in self.moving_boundaries.iter() {
+            let (distance, normal) = moving_boundary.shape.signed_distance(position);
+            if distance > 0.0 && distance < h {
+                points.push(position - normal * distance);
+            }
+        }
+
+        points
+    }
+
+    /// Computes the adhesion acceleration pulling particle `i` towards the nearby boundaries.
+    fn calculate_adhesion_force(&self, i: usize) -> Vector2 {
+        let mut adhesion_force = Vector2::zero();
+        let position = self.particles.predicted_positions[i];
+
+        for point in self.closest_boundary_points(position) {
+            let offset = position - point;
+            let distance = offset.magnitude();
+
+            if distance <= f32::EPSILON {
+                continue;
+            }
+
+            adhesion_force -= offset / distance * self.particles.target_density * self.adhesion_kernel(distance);
+        }
+
+        adhesion_force * self.adhesion_strength
+    }
+
+    /// Velocity change of particle `i` during `dt` from the pointer at `position`.
+    ///
+    /// Within `radius`, the velocity relaxes exponentially toward `strength` pointing at the pointer, so that
+    /// the change over a frame does not depend on the number of substeps it is split in.
+    pub(super) fn calculate_interaction_impulse(&self, position: Vector2, radius: f32, strength: f32, i: usize, dt: f32) -> Vector2 {
+        let mut interaction_impulse = Vector2::zero();
         let dst = self.particles.positions[i].distance_to(position);
 
         if dst < radius {
             let direction = if dst <= f32::EPSILON { Vector2::zero() } else { (position - self.particles.positions[i]).normalize().unwrap() };
             let center_t = 1.0 - dst / radius;
-            interaction_force += (direction * strength - self.particles.velocities[i]) * center_t;
+            let relaxation = 1.0 - (-INTERACTION_RATE * center_t * dt).exp();
+            interaction_impulse += (direction * strength - self.particles.velocities[i]) * relaxation;
         }
 
-        interaction_force * if self.interactive_force_mode { 1.0 } else { -0.15 }
+        interaction_impulse * if self.interactive_force_mode { 1.0 } else { -0.15 }
+    }
+
+    /// Samples the box walls and the obstacles with boundary particles spaced by the particle radius,
+    /// then computes their volumes. Must be called whenever the boundaries or the smoothing radius change.
+    pub fn sample_boundary_particles(&mut self) {
+        self.boundary_particles.clear();
+
+        if !self.use_boundary_particles {
+            return;
+        }
+
+        let spacing = self.particles.radius.max(1.0);
+        let corners = [
+            Vector2::zero(),
+            Vector2::new(self.box_bound_x, 0.0),
+            Vector2::new(self.box_bound_x, self.box_bound_y),
+            Vector2::new(0.0, self.box_bound_y),
+        ];
+
+        // Only the walls of the non periodic axes
+        if !self.particles.periodic.y {
+            self.boundary_particles.sample_segment(corners[0], corners[1], spacing);
+            self.boundary_particles.sample_segment(corners[2], corners[3], spacing);
+        }
+        if !self.particles.periodic.x {
+            self.boundary_particles.sample_segment(corners[1], corners[2], spacing);
+            self.boundary_particles.sample_segment(corners[3], corners[0], spacing);
+        }
+
+        for obstacle in self.obstacles.iter() {
+            self.boundary_particles.sample_polygon(&obstacle.outline(spacing), spacing);
+        }
+
+        let mut boundary_particles = std::mem::take(&mut self.boundary_particles);
+        boundary_particles.update(self.particles.smoothing_radius, |distance| self.smoothing_kernel(distance));
+        self.boundary_particles = boundary_particles;
+    }
+
+    /// Samples every moving boundary at its current position and resets the force and torque accumulated on it.
+    pub fn sample_moving_boundaries(&mut self) {
+        let spacing = self.particles.radius.max(1.0);
+        let mut moving_boundaries = std::mem::take(&mut self.moving_boundaries);
+
+        for moving_boundary in moving_boundaries.iter_mut() {
+            moving_boundary.force = Vector2::zero();
+            moving_boundary.torque = 0.0;
+            moving_boundary.boundary_particles.clear();
+            moving_boundary.boundary_particles.sample_polygon(&moving_boundary.shape.outline(spacing), spacing);
+            moving_boundary.boundary_particles.update(self.particles.smoothing_radius, |distance| self.smoothing_kernel(distance));
+        }
+
+        self.moving_boundaries = moving_boundaries;
+    }
+
+    /// Computes the pressure force exerted on particle `i` by every boundary particle of the moving boundaries.
+    ///
+    /// # Returns
+    ///
+    /// A list of `(moving boundary index, boundary particle position, pressure force)`.
+    fn calculate_moving_boundary_pressure_forces(&self, i: usize) -> Vec<(usize, Vector2, Vector2)> {
+        let mut forces = Vec::new();
+        let position = self.particles.predicted_positions[i];
+        let pressure = self.particles.calculate_pressure(self.particles.densities[i]);
+
+        for (k, moving_boundary) in self.moving_boundaries.iter().enumerate() {
+            let boundary_particles = &moving_boundary.boundary_particles;
+            for b in boundary_particles.neighbors(position, self.particles.smoothing_radius) {
+                let offset = position - boundary_particles.positions[b];
+                let distance = offset.magnitude();
+                if distance <= f32::EPSILON {
+                    continue;
+                }
+                let slope = self.smoothing_kernel_derivative(distance);
+                forces.push((k, boundary_particles.positions[b], offset / distance * pressure * slope * boundary_particles.volumes[b]));
+            }
+        }
+
+        forces
+    }
+
+    /// Pushes particle `i` out of the moving boundaries, reflecting its velocity relative to the body,
+    /// and transfers the exchanged momentum to the body as a force over `dt`.
+    pub fn resolve_moving_boundary_collision(&mut self, i: usize, dt: f32) {
+        let mut position = self.particles.positions[i];
+        let mut velocity = self.particles.velocities[i];
+
+        for moving_boundary in self.moving_boundaries.iter_mut() {
+            let (distance, normal) = moving_boundary.shape.signed_distance(position);
+            let penetration = distance - self.particles.radius;
+
+            if penetration >= 0.0 {
+                continue;
+            }
+
+            position -= normal * penetration;
+
+            let surface_point = position - normal * self.particles.radius;
+            let body_velocity = moving_boundary.velocity_at(surface_point);
+            let relative_velocity = velocity - body_velocity;
+            let normal_speed = relative_velocity * normal;
+
+            if normal_speed < 0.0 {
+                let tangential_velocity = relative_velocity - normal * normal_speed;
+                let new_velocity = body_velocity + tangential_velocity * (1.0 - moving_boundary.friction) - normal * normal_speed * moving_boundary.restitution;
+                if dt > 0.0 {
+                    moving_boundary.apply_force((velocity - new_velocity) * self.particles.mass / dt, surface_point);
+                }
+                velocity = new_velocity;
+            }
+        }
+
+        self.particles.positions[i] = position;
+        self.particles.velocities[i] = velocity;
     }
 
     pub fn resolve_collision(&mut self, i: usize) {
         let mut position = self.particles.positions[i];
         let mut velocity = self.particles.velocities[i];
 
-        let coefficient_of_restitution = 0.95;
+        let coefficient_of_restitution = self.collision_restitution;
 
-        if position.x - self.particles.radius < 0.0 {
+        if self.particles.periodic.x {
+            position.x = position.x.rem_euclid(self.box_bound_x);
+        } else if position.x - self.particles.radius < 0.0 {
             position.x = self.particles.radius;
             velocity.x = -velocity.x * coefficient_of_restitution;
         } else if position.x + self.particles.radius > self.box_bound_x {
@@ -337,7 +812,9 @@ impl Fluid {
             velocity.x = -velocity.x * coefficient_of_restitution;
         }
 
-        if position.y - self.particles.radius < 0.0 {
+        if self.particles.periodic.y {
+            position.y = position.y.rem_euclid(self.box_bound_y);
+        } else if position.y - self.particles.radius < 0.0 {
             position.y = self.particles.radius;
             velocity.y = -velocity.y * coefficient_of_restitution;
         } else if position.y + self.particles.radius > self.box_bound_y {
@@ -345,80 +822,289 @@ impl Fluid {
             velocity.y = -velocity.y * coefficient_of_restitution;
         }
 
+        for obstacle in self.obstacles.iter() {
+            let (distance, normal) = obstacle.signed_distance(position);
+            let penetration = distance - self.particles.radius;
+
+            if penetration >= 0.0 {
+                continue;
+            }
+
+            // Project the particle back onto the surface
+            position -= normal * penetration;
+
+            // Reflect the normal velocity and damp the tangential one
+            let normal_speed = velocity * normal;
+            if normal_speed < 0.0 {
+                let tangential_velocity = velocity - normal * normal_speed;
+                velocity = tangential_velocity * (1.0 - obstacle.friction) - normal * normal_speed * obstacle.restitution;
+            }
+        }
+
         self.particles.positions[i] = position;
         self.particles.velocities[i] = velocity;
     }
 
+    /// Spawns the particles of every emitter, up to `max_particles`, then deletes the particles inside a sink.
+    pub fn update_emitters_and_sinks(&mut self, dt: f32) {
+        for emitter in self.emitters.iter_mut() {
+            for (position, velocity) in emitter.emit(dt, self.max_particles.saturating_sub(self.particles.len())) {
+                self.particles.spawn(position, velocity, emitter.phase);
+            }
+        }
+
+        if self.sinks.is_empty() {
+            return;
+        }
+
+        // Iterate backwards so that swapped-in particles have already been checked
+        for i in (0..self.particles.len()).rev() {
+            let position = self.particles.positions[i];
+            if self.sinks.iter().any(|sink| sink.contains(position)) {
+                self.particles.swap_remove(i);
+                self.remap_springs_after_removal(i);
+            }
+        }
+    }
+
+    /// Computes the largest stable timestep from the CFL condition on the fastest particle
+    /// and from the force condition on the largest acceleration of the last update.
+    pub fn calculate_stable_timestep(&self) -> f32 {
+        let smoothing_radius = self.particles.smoothing_radius;
+        let max_speed = self.particles.velocities.iter().map(|velocity| velocity.magnitude()).fold(0.0, f32::max);
+        let mut stable_dt = f32::INFINITY;
+
+        if max_speed > f32::EPSILON {
+            stable_dt = stable_dt.min(self.cfl_factor * smoothing_radius / max_speed);
+        }
+
+        if self.max_acceleration > f32::EPSILON {
+            stable_dt = stable_dt.min(self.cfl_factor * (smoothing_radius / self.max_acceleration).sqrt());
+        }
+
+        stable_dt
+    }
+
+    /// Splits `dt` into the number of substeps needed to stay stable, up to `max_substeps`.
+    ///
+    /// # Returns
+    ///
+    /// The duration of a substep, the count being stored in `substeps`.
+    pub fn plan_substeps(&mut self, dt: f32) -> f32 {
+        let substeps = (dt / self.calculate_stable_timestep()).ceil();
+        self.substeps = if substeps.is_finite() { (substeps as usize).clamp(1, self.max_substeps.max(1)) } else { 1 };
+
+        dt / self.substeps as f32
+    }
+
+    /// Advances the fluid by `dt`, substepping it when the particles move or accelerate too fast for a single update.
+    pub fn step(&mut self, dt: f32) -> () {
+        let substep_dt = self.plan_substeps(dt);
+
+        for _ in 0..self.substeps {
+            self.update(substep_dt);
+        }
+
+        self.update_colors();
+        self.update_surface();
+    }
+
     pub fn update(&mut self, dt: f32) -> () {
-        if self.interactive_force {
-            // Apply gravity and predicted positions
+        // Emit and remove particles
+        self.update_emitters_and_sinks(dt);
+
+        self.metrics = SolverMetrics::default();
+        // The pointer is applied before measuring the accelerations, as it would otherwise drive the substeps
+        self.apply_interaction_force(dt);
+        let previous_velocities = self.particles.velocities.clone();
+
+        match self.solver {
+            FluidSolver::ExplicitSph => self.update_explicit(dt),
+            FluidSolver::PositionBased => self.update_position_based(dt),
+            FluidSolver::Implicit => self.update_implicit(dt),
+        }
+
+        // Measure the largest acceleration for the next timestep
+        if dt > 0.0 {
+            self.max_acceleration = self.particles.velocities.iter().zip(previous_velocities.iter())
+                .map(|(velocity, previous_velocity)| (*velocity - *previous_velocity).magnitude() / dt)
+                .fold(0.0, f32::max);
+        }
+    }
+
+    /// Pulls or pushes the particles around the pointer while the interactive force is on.
+    fn apply_interaction_force(&mut self, dt: f32) {
+        if !self.interactive_force {
+            return;
+        }
+
+        (0..self.particles.len()).for_each(|i| {
+            let interaction_impulse = self.calculate_interaction_impulse(self.interactive_force_position, 100.0, 150.0, i, dt);
+            self.particles.velocities[i] += interaction_impulse;
+        });
+    }
+
+    /// Applies gravity and the force fields to the velocities, then predicts the positions.
+    pub(super) fn apply_external_forces(&mut self, dt: f32) {
+        let gravity = Vector2::down() * self.gravity;
+        let force_fields = &self.force_fields;
+        self.external_motion = (0..self.particles.len()).map(|i| {
+            self.integrator.step(self.particles.positions[i], self.particles.velocities[i], dt, |position, velocity| {
+                gravity + total_acceleration(force_fields, position, velocity)
+            })
+        }).collect();
+
+        for (i, (predicted_position, velocity)) in self.external_motion.iter().enumerate() {
+            self.particles.predicted_positions[i] = *predicted_position;
+            self.particles.velocities[i] = *velocity;
+        }
+    }
+
+    /// Moves particle `i` to the end of the step: where the integrator took it under the external forces,
+    /// plus the velocity change of the internal forces since then. With the semi-implicit Euler scheme,
+    /// this is the usual `position + velocity * dt`.
+    pub(super) fn advance_position(&mut self, i: usize, dt: f32) {
+        let (position, velocity) = self.external_motion[i];
+        self.particles.positions[i] = position + (self.particles.velocities[i] - velocity) * dt;
+    }
+
+    /// Velocity of particle `i` once a position-based solver has corrected its predicted position: the velocity
+    /// given by the integrator plus the correction over `dt`. With the semi-implicit Euler scheme, this is the
+    /// usual `(predicted_position - position) / dt`.
+    pub(super) fn corrected_velocity(&self, i: usize, dt: f32) -> Vector2 {
+        let (position, velocity) = self.external_motion[i];
+        velocity + (self.particles.predicted_positions[i] - position) / dt
+    }
+
+    /// Applies the surface tension and adhesion forces to the velocities, when enabled.
+    pub(super) fn apply_surface_forces(&mut self, dt: f32) {
+        // Calculate and apply surface tension forces
+        if self.surface_tension_strength > 0.0 {
+            let normals: Vec<Vector2> = (0..self.particles.len()).map(|i| self.calculate_surface_normal(i)).collect();
             (0..self.particles.len()).for_each(|i| {
-                self.particles.velocities[i] += Vector2::down() * self.gravity * dt;
-                let interaction_force = self.calculate_interaction_force(self.interactive_force_position, 100.0, 150.0, i);
-                self.particles.velocities[i] += interaction_force;
-                self.particles.predicted_positions[i] = self.particles.positions[i] + self.particles.velocities[i] * dt;
+                let surface_tension_force = self.calculate_surface_tension_force(i, &normals);
+                self.particles.velocities[i] += surface_tension_force * dt;
             });
-        } else {
-            // Apply gravity and predicted positions
+        }
+
+        // Calculate and apply adhesion forces
+        if self.adhesion_strength > 0.0 {
             (0..self.particles.len()).for_each(|i| {
-                self.particles.velocities[i] += Vector2::down() * self.gravity * dt;
-                self.particles.predicted_positions[i] = self.particles.positions[i] + self.particles.velocities[i] * dt;
+                let adhesion_force = self.calculate_adhesion_force(i);
+                self.particles.velocities[i] += adhesion_force * dt;
             });
         }
+    }
+
+    /// Performs a step of weakly compressible SPH with explicit pressure forces.
+    fn update_explicit(&mut self, dt: f32) {
+        self.apply_external_forces(dt);
 
         // Update spatial lookup
         self.particles.update_spatial_lookup();
 
-        if self.visual_filter == 3 {
-            // Calculate densities
-            (0..self.particles.len()).for_each(|i| {
-                self.particles.densities[i] = self.calculate_density(i);
-                self.particles.colors[i] = self.density_gradient.at((self.particles.densities[i] * 100.0 / self.particles.target_density * 2.0) as f64).to_hex_string();
-            });
-        } else {
-            // Calculate densities
-            (0..self.particles.len()).for_each(|i| {
-                self.particles.densities[i] = self.calculate_density(i);
-            });
+        // Sample the moving boundaries at their current position
+        if !self.moving_boundaries.is_empty() {
+            self.sample_moving_boundaries();
         }
 
-        if self.visual_filter == 1 {
-            // Calculate and apply pressure forces
-            (0..self.particles.len()).for_each(|i| {
-                let pressure_force = self.calculate_pressure_force(i);
-                assert!(self.particles.densities[i] != 0.0, "density should not be zero");
-                let pressure_acceleration = pressure_force / self.particles.densities[i];
-                self.particles.velocities[i] += pressure_acceleration * dt;
-                self.particles.colors[i] = self.velocity_gradient.at((self.particles.velocities[i].magnitude() / 100.0) as f64).to_hex_string();
-            });
-        } else if self.visual_filter == 2 {
-            // Calculate and apply pressure forces
-            (0..self.particles.len()).for_each(|i| {
-                let pressure_force = self.calculate_pressure_force(i);
-                self.particles.colors[i] = self.velocity_gradient.at(pressure_force.magnitude() as f64).to_hex_string();
-                assert!(self.particles.densities[i] != 0.0, "density should not be zero");
-                let pressure_acceleration = pressure_force / self.particles.densities[i];
-                self.particles.velocities[i] += pressure_acceleration * dt;
-            });
-        }
-        else {
-            // Calculate and apply pressure forces
+        // Calculate densities and pressures
+        (0..self.particles.len()).for_each(|i| {
+            self.particles.densities[i] = self.calculate_density(i);
+            self.particles.pressures[i] = self.particles.calculate_pressure(self.particles.densities[i]);
+        });
+
+        // Calculate and apply pressure forces
+        (0..self.particles.len()).for_each(|i| {
+            let pressure_force = self.calculate_pressure_force(i);
+            assert!(self.particles.densities[i] != 0.0, "density should not be zero");
+            let pressure_acceleration = pressure_force / self.particles.densities[i];
+            self.particles.velocities[i] += pressure_acceleration * dt;
+        });
+
+        // Apply the pressure of the moving boundaries and its reaction on the bodies
+        if !self.moving_boundaries.is_empty() {
             (0..self.particles.len()).for_each(|i| {
-                let pressure_force = self.calculate_pressure_force(i);
-                assert!(self.particles.densities[i] != 0.0, "density should not be zero");
-                let pressure_acceleration = pressure_force / self.particles.densities[i];
-                self.particles.velocities[i] += pressure_acceleration * dt;
+                for (k, point, pressure_force) in self.calculate_moving_boundary_pressure_forces(i) {
+                    let pressure_acceleration = pressure_force / self.particles.densities[i];
+                    self.particles.velocities[i] += pressure_acceleration * dt;
+                    self.moving_boundaries[k].apply_force(pressure_acceleration * -self.particles.mass, point);
+                }
             });
         }
 
+        self.apply_surface_forces(dt);
+        self.apply_heat_transfer(dt);
+        self.apply_springs(dt);
+
         // Update positions and resolve collisions
         (0..self.particles.len()).for_each(|i| {
             let viscosity_force = self.calculate_viscosity_force(i);
             self.particles.velocities[i] += viscosity_force * dt;
-            self.particles.positions[i] += self.particles.velocities[i] * dt;
+            self.advance_position(i, dt);
             self.resolve_collision(i);
+            self.resolve_moving_boundary_collision(i, dt);
         });
 
         //dbg!(&self.particles);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pulled_particle_velocity(substeps: usize) -> Vector2 {
+        let gradient = colorgrad::CustomGradient::new().html_colors(&["#000000", "#ffffff"]).build().unwrap();
+        let mut fluid = Fluid::new(gradient);
+        fluid.box_bound_x = 800.0;
+        fluid.box_bound_y = 600.0;
+        fluid.particles.push(Vector2::new(400.0, 300.0));
+        fluid.interactive_force = true;
+        fluid.interactive_force_position = Vector2::new(450.0, 300.0);
+
+        for _ in 0..substeps {
+            fluid.update(1.0 / 60.0 / substeps as f32);
+        }
+
+        fluid.particles.velocities[0]
+    }
+
+    fn fallen_distance(solver: FluidSolver, integrator: Integrator, steps: usize, dt: f32) -> f32 {
+        let gradient = colorgrad::CustomGradient::new().html_colors(&["#000000", "#ffffff"]).build().unwrap();
+        let mut fluid = Fluid::new(gradient);
+        fluid.solver = solver;
+        fluid.integrator = integrator;
+        fluid.gravity = 100.0;
+        fluid.particles.push(Vector2::new(400.0, 300.0));
+
+        for _ in 0..steps {
+            fluid.update(dt);
+        }
+
+        fluid.particles.positions[0].distance_to(Vector2::new(400.0, 300.0))
+    }
+
+    #[test]
+    fn integrator_moves_the_particles_of_every_solver() {
+        let (steps, dt) = (30, 1.0 / 60.0);
+        let exact = 0.5 * 100.0 * (steps as f32 * dt).powi(2);
+
+        for solver in [FluidSolver::ExplicitSph, FluidSolver::PositionBased, FluidSolver::Implicit] {
+            let runge_kutta = fallen_distance(solver, Integrator::Rk4, steps, dt);
+            let semi_implicit = fallen_distance(solver, Integrator::SemiImplicitEuler, steps, dt);
+
+            assert!((runge_kutta - exact).abs() < 1e-2, "{:?}: fell {} with RK4, {} expected", solver, runge_kutta, exact);
+            assert!((semi_implicit - exact).abs() > 0.3, "{:?}: fell {} with semi-implicit Euler, {} expected", solver, semi_implicit, exact);
+        }
+    }
+
+    #[test]
+    fn pointer_pull_does_not_depend_on_the_substeps() {
+        let single = pulled_particle_velocity(1);
+        let split = pulled_particle_velocity(16);
+
+        assert!(single.magnitude() > 10.0, "the pointer barely pulls: {:?}", single);
+        assert!((single - split).magnitude() < 0.1 * single.magnitude(), "{:?} with 1 substep, {:?} with 16", single, split);
+    }
+}
@@ -1,3 +1,15 @@
 pub mod data;
 pub mod system;
 pub mod main;
+pub mod obstacle;
+pub mod boundary;
+pub mod emitter;
+pub mod moving_boundary;
+pub mod pbf;
+pub mod iisph;
+pub mod thermal;
+pub mod visualization;
+pub mod surface;
+pub mod layout;
+pub mod kernel;
+pub mod viscoelastic;
@@ -0,0 +1,48 @@
+use crate::core::sciences::maths::Vector2;
+
+use super::boundary::BoundaryParticles;
+use super::obstacle::ObstacleShape;
+
+/// A rigid boundary moving through the fluid, such as a floating body.
+///
+/// The fluid treats it like an obstacle sampled with boundary particles, using its velocity for the
+/// collision response, and accumulates in `force` and `torque` the reaction of the fluid on the body
+/// during each update, so that the owner of the body can integrate it.
+pub struct MovingBoundary {
+    pub shape: ObstacleShape,
+    pub center: Vector2,
+    pub velocity: Vector2,
+    pub angular_velocity: f32,
+    pub restitution: f32,
+    pub friction: f32,
+    pub force: Vector2,
+    pub torque: f32,
+    pub boundary_particles: BoundaryParticles,
+}
+
+impl MovingBoundary {
+    pub fn new(shape: ObstacleShape, center: Vector2, velocity: Vector2, angular_velocity: f32, restitution: f32, friction: f32) -> Self {
+        Self {
+            shape,
+            center,
+            velocity,
+            angular_velocity,
+            restitution,
+            friction,
+            force: Vector2::zero(),
+            torque: 0.0,
+            boundary_particles: BoundaryParticles::new(),
+        }
+    }
+
+    /// Returns the velocity of the rigid body at `point`, including the rotation.
+    pub fn velocity_at(&self, point: Vector2) -> Vector2 {
+        self.velocity + (point - self.center).perpendicular() * self.angular_velocity
+    }
+
+    /// Accumulates a force applied at `point` as a force and a torque around the center.
+    pub fn apply_force(&mut self, force: Vector2, point: Vector2) {
+        self.force += force;
+        self.torque += (point - self.center).cross(force);
+    }
+}
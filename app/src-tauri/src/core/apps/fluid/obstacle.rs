@@ -0,0 +1,150 @@
+use std::f32::consts::PI;
+
+use serde::{Serialize, Deserialize};
+
+use crate::core::sciences::maths::Vector2;
+
+/// Geometry of a region of the fluid domain, used by obstacles and sinks.
+///
+/// Serialized with an internal `type` tag so the frontend can send, e.g.
+/// `{ "type": "circle", "center": { "x": 400, "y": 300 }, "radius": 50 }`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObstacleShape {
+    Circle { center: Vector2, radius: f32 },
+    Rectangle { center: Vector2, size: Vector2, rotation: f32 },
+    Polygon { vertices: Vec<Vector2> },
+}
+
+impl ObstacleShape {
+    /// Computes the signed distance from `point` to the shape surface.
+    ///
+    /// # Returns
+    ///
+    /// A tuple `(distance, normal)` where `distance` is negative inside the shape
+    /// and `normal` is the unit outward normal of the closest surface point.
+    pub fn signed_distance(&self, point: Vector2) -> (f32, Vector2) {
+        match self {
+            ObstacleShape::Circle { center, radius } => {
+                let offset = point - *center;
+                let distance = offset.magnitude();
+                let normal = if distance <= f32::EPSILON { Vector2::up() } else { offset / distance };
+                (distance - radius, normal)
+            },
+            ObstacleShape::Rectangle { center, size, rotation } => {
+                let local = (point - *center).rotate(-rotation);
+                let half = *size / 2.0;
+                let qx = local.x.abs() - half.x;
+                let qy = local.y.abs() - half.y;
+
+                let (distance, local_normal) = if qx > 0.0 || qy > 0.0 {
+                    let outside = Vector2::new(qx.max(0.0) * local.x.signum(), qy.max(0.0) * local.y.signum());
+                    let distance = outside.magnitude();
+                    (distance, outside / distance)
+                } else if qx > qy {
+                    (qx, Vector2::new(local.x.signum(), 0.0))
+                } else {
+                    (qy, Vector2::new(0.0, local.y.signum()))
+                };
+
+                (distance, local_normal.rotate(*rotation))
+            },
+            ObstacleShape::Polygon { vertices } => polygon_signed_distance(vertices, point),
+        }
+    }
+
+    /// Returns the vertices of a closed polyline following the shape surface,
+    /// with circles discretized so that consecutive vertices are about `spacing` apart.
+    pub fn outline(&self, spacing: f32) -> Vec<Vector2> {
+        match self {
+            ObstacleShape::Circle { center, radius } => {
+                let count = (2.0 * PI * radius / spacing).ceil().max(3.0) as usize;
+                (0..count).map(|k| {
+                    let angle = 2.0 * PI * k as f32 / count as f32;
+                    *center + Vector2::new(angle.cos(), angle.sin()) * *radius
+                }).collect()
+            },
+            ObstacleShape::Rectangle { center, size, rotation } => {
+                let half = *size / 2.0;
+                [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].iter()
+                    .map(|(sx, sy)| *center + Vector2::new(sx * half.x, sy * half.y).rotate(*rotation))
+                    .collect()
+            },
+            ObstacleShape::Polygon { vertices } => vertices.clone(),
+        }
+    }
+}
+
+/// A static obstacle with its own collision response coefficients.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Obstacle {
+    pub shape: ObstacleShape,
+    pub restitution: f32,
+    pub friction: f32,
+}
+
+impl Obstacle {
+    pub fn new(shape: ObstacleShape, restitution: f32, friction: f32) -> Self {
+        Self {
+            shape,
+            restitution,
+            friction,
+        }
+    }
+
+    pub fn signed_distance(&self, point: Vector2) -> (f32, Vector2) {
+        self.shape.signed_distance(point)
+    }
+
+    pub fn outline(&self, spacing: f32) -> Vec<Vector2> {
+        self.shape.outline(spacing)
+    }
+
+    /// Returns the closest point of the obstacle surface to `point`.
+    pub fn closest_point(&self, point: Vector2) -> Vector2 {
+        let (distance, normal) = self.signed_distance(point);
+        point - normal * distance
+    }
+}
+
+/// Signed distance to a simple polygon, negative inside, using the even-odd rule for the sign.
+fn polygon_signed_distance(vertices: &[Vector2], point: Vector2) -> (f32, Vector2) {
+    if vertices.len() < 3 {
+        return (f32::MAX, Vector2::up());
+    }
+
+    let mut min_distance = f32::MAX;
+    let mut closest = vertices[0];
+    let mut inside = false;
+
+    for i in 0..vertices.len() {
+        let a = vertices[i];
+        let b = vertices[(i + 1) % vertices.len()];
+        let edge = b - a;
+        let length_squared = edge.magnitude_squared();
+
+        let t = if length_squared > 0.0 { (((point - a) * edge) / length_squared).clamp(0.0, 1.0) } else { 0.0 };
+        let candidate = a + edge * t;
+        let distance = point.distance_to(candidate);
+
+        if distance < min_distance {
+            min_distance = distance;
+            closest = candidate;
+        }
+
+        if (a.y > point.y) != (b.y > point.y) && point.x < a.x + (point.y - a.y) / (b.y - a.y) * edge.x {
+            inside = !inside;
+        }
+    }
+
+    let direction = match (point - closest).normalize() {
+        Ok(direction) => direction,
+        Err(_) => Vector2::up(),
+    };
+
+    if inside {
+        (-min_distance, direction.opposite())
+    } else {
+        (min_distance, direction)
+    }
+}
@@ -0,0 +1,258 @@
+use crate::core::sciences::maths::Vector2;
+
+use super::main::{Fluid, SolverMetrics};
+
+/// Relaxation added to the denominator of the constraint multipliers, avoiding divisions by zero.
+const CONSTRAINT_RELAXATION: f32 = 1e-6;
+
+/// Position corrections caused by moving boundary particles, as
+/// `(moving boundary index, boundary particle position, correction)`.
+type BoundaryCorrections = Vec<(usize, Vector2, Vector2)>;
+
+/// Position Based Fluids solver (Macklin & Müller 2013).
+///
+/// Instead of deriving a pressure force from the density, each particle carries a density
+/// constraint `C_i = ρ_i / ρ0 - 1` that is solved iteratively on the predicted positions,
+/// which stays stable with large timesteps.
+impl Fluid {
+    /// Density of particles at rest on a square lattice spaced by one particle diameter.
+    ///
+    /// Used as rest density by the incompressible solvers, since `target_density` is tuned
+    /// for the equation of state of the explicit solver.
+    pub fn lattice_rest_density(&self) -> f32 {
+        let spacing = 2.0 * self.particles.radius;
+        let count = (self.particles.smoothing_radius / spacing).ceil() as i32;
+        let mut density = self.smoothing_kernel(0.0);
+
+        for x in -count..=count {
+            for y in -count..=count {
+                if x == 0 && y == 0 {
+                    continue;
+                }
+                let distance = Vector2::new(x as f32, y as f32).magnitude() * spacing;
+                density += self.particles.mass * self.smoothing_kernel(distance);
+            }
+        }
+
+        density
+    }
+
+    /// Returns the boundary particles within the smoothing radius of `position`, static ones only if enabled.
+    ///
+    /// # Returns
+    ///
+    /// A list of `(moving boundary index, boundary particle position, boundary particle volume)`,
+    /// the index being `None` for the static boundary particles.
+    pub(super) fn boundary_neighbors(&self, position: Vector2) -> Vec<(Option<usize>, Vector2, f32)> {
+        let mut neighbors = Vec::new();
+        let radius = self.particles.smoothing_radius;
+
+        if self.use_boundary_particles {
+            for b in self.boundary_particles.neighbors(position, radius) {
+                neighbors.push((None, self.boundary_particles.positions[b], self.boundary_particles.volumes[b]));
+            }
+        }
+
+        for (k, moving_boundary) in self.moving_boundaries.iter().enumerate() {
+            let boundary_particles = &moving_boundary.boundary_particles;
+            for b in boundary_particles.neighbors(position, radius) {
+                neighbors.push((Some(k), boundary_particles.positions[b], boundary_particles.volumes[b]));
+            }
+        }
+
+        neighbors
+    }
+
+    /// Gradient of the density kernel with respect to the first position.
+    pub(super) fn kernel_gradient(&self, offset: Vector2) -> Vector2 {
+        let distance = offset.magnitude();
+        if distance <= f32::EPSILON {
+            return Vector2::zero();
+        }
+        offset / distance * self.smoothing_kernel_derivative(distance)
+    }
+
+    /// Density of particle `i` at its predicted position, from a precomputed neighbour list.
+    pub(super) fn calculate_predicted_density(&self, i: usize, neighbors: &[usize], rest_density: f32) -> f32 {
+        let position = self.particles.predicted_positions[i];
+        let mut density = self.smoothing_kernel(0.0);
+
+        for other_i in neighbors {
+            let distance = self.particles.distance(position, self.particles.predicted_positions[*other_i]);
+            density += self.particles.mass * self.smoothing_kernel(distance);
+        }
+
+        for (_, boundary_position, volume) in self.boundary_neighbors(position) {
+            density += rest_density * volume * self.smoothing_kernel(position.distance_to(boundary_position));
+        }
+
+        density
+    }
+
+    /// Computes the Lagrange multiplier of the density constraint of particle `i`.
+    ///
+    /// The constraint is unilateral: under-dense particles, at the free surface, are not pulled together.
+    fn calculate_constraint_lambda(&self, i: usize, neighbors: &[usize], rest_density: f32) -> f32 {
+        let constraint = (self.particles.densities[i] / rest_density - 1.0).max(0.0);
+
+        if constraint == 0.0 {
+            return 0.0;
+        }
+
+        let position = self.particles.predicted_positions[i];
+        let mut gradient_i = Vector2::zero();
+        let mut sum_gradient_squared = 0.0;
+
+        for other_i in neighbors {
+            let gradient = self.kernel_gradient(self.particles.offset(position, self.particles.predicted_positions[*other_i])) * (self.particles.mass / rest_density);
+            gradient_i += gradient;
+            sum_gradient_squared += gradient.magnitude_squared();
+        }
+
+        for (_, boundary_position, volume) in self.boundary_neighbors(position) {
+            gradient_i += self.kernel_gradient(position - boundary_position) * volume;
+        }
+
+        -constraint / (sum_gradient_squared + gradient_i.magnitude_squared() + CONSTRAINT_RELAXATION)
+    }
+
+    /// Computes the position correction of particle `i`, including the artificial pressure term
+    /// which keeps particles from clustering.
+    ///
+    /// # Returns
+    ///
+    /// The correction and the corrections caused by each moving boundary particle.
+    fn calculate_position_correction(&self, i: usize, neighbors: &[usize], lambdas: &[f32], rest_density: f32) -> (Vector2, BoundaryCorrections) {
+        let position = self.particles.predicted_positions[i];
+        let reference_kernel = self.smoothing_kernel(0.2 * self.particles.smoothing_radius);
+        let mut correction = Vector2::zero();
+        let mut boundary_corrections = Vec::new();
+
+        for other_i in neighbors {
+            let offset = self.particles.offset(position, self.particles.predicted_positions[*other_i]);
+            let ratio = if reference_kernel > 0.0 { self.smoothing_kernel(offset.magnitude()) / reference_kernel } else { 0.0 };
+            let artificial_pressure = -self.artificial_pressure_strength * ratio.powi(4);
+
+            correction += self.kernel_gradient(offset) * ((lambdas[i] + lambdas[*other_i] + artificial_pressure) * self.particles.mass / rest_density);
+        }
+
+        for (k, boundary_position, volume) in self.boundary_neighbors(position) {
+            let boundary_correction = self.kernel_gradient(position - boundary_position) * (lambdas[i] * volume);
+            correction += boundary_correction;
+
+            if let Some(k) = k {
+                boundary_corrections.push((k, boundary_position, boundary_correction));
+            }
+        }
+
+        (correction, boundary_corrections)
+    }
+
+    /// Keeps the predicted position of particle `i` inside the box walls and outside of the obstacles.
+    fn constrain_predicted_position(&mut self, i: usize) {
+        let radius = self.particles.radius;
+        let mut position = self.particles.predicted_positions[i];
+
+        // The periodic axes are wrapped with the positions, once the velocities are known
+        if !self.particles.periodic.x {
+            position.x = position.x.clamp(radius, (self.box_bound_x - radius).max(radius));
+        }
+        if !self.particles.periodic.y {
+            position.y = position.y.clamp(radius, (self.box_bound_y - radius).max(radius));
+        }
+
+        for obstacle in self.obstacles.iter() {
+            let (distance, normal) = obstacle.signed_distance(position);
+            if distance < radius {
+                position += normal * (radius - distance);
+            }
+        }
+
+        for moving_boundary in self.moving_boundaries.iter() {
+            let (distance, normal) = moving_boundary.shape.signed_distance(position);
+            if distance < radius {
+                position += normal * (radius - distance);
+            }
+        }
+
+        self.particles.predicted_positions[i] = position;
+    }
+
+    /// Performs a step of Position Based Fluids.
+    pub fn update_position_based(&mut self, dt: f32) {
+        if dt <= 0.0 {
+            return;
+        }
+
+        self.apply_external_forces(dt);
+
+        // Update spatial lookup and neighbour lists, kept for every iteration
+        self.particles.update_spatial_lookup();
+
+        if !self.moving_boundaries.is_empty() {
+            self.sample_moving_boundaries();
+        }
+
+        let rest_density = self.lattice_rest_density();
+        let neighbors: Vec<Vec<usize>> = (0..self.particles.len()).map(|i| self.in_radius_neighbors_search(i)).collect();
+        let mut lambdas = vec![0.0; self.particles.len()];
+        let mut metrics = SolverMetrics::default();
+
+        for _ in 0..self.solver_iterations.max(1) {
+            // Calculate densities and constraint multipliers
+            (0..self.particles.len()).for_each(|i| {
+                self.particles.densities[i] = self.calculate_predicted_density(i, &neighbors[i], rest_density);
+            });
+            (0..self.particles.len()).for_each(|i| {
+                lambdas[i] = self.calculate_constraint_lambda(i, &neighbors[i], rest_density);
+            });
+
+            let total_error: f32 = self.particles.densities.iter().map(|density| (density / rest_density - 1.0).max(0.0)).sum();
+            metrics.iterations += 1;
+            metrics.density_error = if self.particles.len() > 0 { total_error / self.particles.len() as f32 } else { 0.0 };
+
+            // Calculate and apply position corrections, the moving boundaries receiving the reaction
+            let corrections: Vec<(Vector2, BoundaryCorrections)> = (0..self.particles.len())
+                .map(|i| self.calculate_position_correction(i, &neighbors[i], &lambdas, rest_density))
+                .collect();
+
+            for (i, (correction, boundary_corrections)) in corrections.into_iter().enumerate() {
+                self.particles.predicted_positions[i] += correction;
+                self.constrain_predicted_position(i);
+
+                for (k, point, boundary_correction) in boundary_corrections {
+                    self.moving_boundaries[k].apply_force(boundary_correction * (-self.particles.mass / (dt * dt)), point);
+                }
+            }
+        }
+
+        self.metrics = metrics;
+
+        // The constraint multipliers act as pressures, negative multipliers meaning compression
+        (0..self.particles.len()).for_each(|i| {
+            self.particles.pressures[i] = -lambdas[i];
+        });
+
+        // Update velocities from the corrected positions
+        (0..self.particles.len()).for_each(|i| {
+            self.particles.velocities[i] = self.corrected_velocity(i, dt);
+        });
+
+        // XSPH viscosity
+        let viscosity: Vec<Vector2> = (0..self.particles.len()).map(|i| self.calculate_viscosity_force(i)).collect();
+        (0..self.particles.len()).for_each(|i| {
+            self.particles.velocities[i] += viscosity[i];
+        });
+
+        self.apply_surface_forces(dt);
+        self.apply_heat_transfer(dt);
+        self.apply_springs(dt);
+
+        // Update positions and resolve collisions
+        (0..self.particles.len()).for_each(|i| {
+            self.particles.positions[i] = self.particles.predicted_positions[i];
+            self.resolve_collision(i);
+            self.resolve_moving_boundary_collision(i, dt);
+        });
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::core::sciences::maths::Vector2;
+
+use super::main::Fluid;
+
+/// A closed iso-contour of the sampled density field.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SurfaceContour {
+    pub points: Vec<Vector2>,
+    /// The contour bounds an empty region inside the liquid, such as a bubble.
+    pub hole: bool,
+}
+
+/// The liquid surface extracted by marching squares, sent as an optional render layer.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct FluidSurface {
+    pub contours: Vec<SurfaceContour>,
+    /// Length of the contours inside the box, the walls excluded.
+    pub free_surface_length: f32,
+    /// Area enclosed by the contours, the holes excluded.
+    pub area: f32,
+}
+
+/// Density field sampled on the nodes of a regular grid, padded with one empty node
+/// outside each wall so that every contour is closed.
+struct DensityGrid {
+    nodes_x: usize,
+    nodes_y: usize,
+    cell_size: Vector2,
+    values: Vec<f32>,
+}
+
+impl DensityGrid {
+    fn node_position(&self, x: usize, y: usize) -> Vector2 {
+        Vector2::new((x as f32 - 1.0) * self.cell_size.x, (y as f32 - 1.0) * self.cell_size.y)
+    }
+
+    fn value(&self, x: usize, y: usize) -> f32 {
+        self.values[y * self.nodes_x + x]
+    }
+
+    /// Id of the horizontal edge starting at node (x, y).
+    fn horizontal_edge(&self, x: usize, y: usize) -> usize {
+        2 * (y * self.nodes_x + x)
+    }
+
+    /// Id of the vertical edge starting at node (x, y).
+    fn vertical_edge(&self, x: usize, y: usize) -> usize {
+        2 * (y * self.nodes_x + x) + 1
+    }
+}
+
+/// Surface extraction, run after the physics of the frame like the visualization stage.
+impl Fluid {
+    /// Samples the SPH density on the nodes of a grid covering the box.
+    fn sample_density_grid(&self) -> DensityGrid {
+        let cells_x = (self.box_bound_x / self.surface_resolution).ceil().max(1.0) as usize;
+        let cells_y = (self.box_bound_y / self.surface_resolution).ceil().max(1.0) as usize;
+        // The cells are stretched to put nodes exactly on the walls
+        let cell_size = Vector2::new(self.box_bound_x / cells_x as f32, self.box_bound_y / cells_y as f32);
+        let nodes_x = cells_x + 3;
+        let nodes_y = cells_y + 3;
+        let mut values = vec![0.0; nodes_x * nodes_y];
+
+        let radius = self.particles.smoothing_radius;
+        for position in self.particles.positions.iter() {
+            // Only the nodes inside the box, the padding ring stays empty
+            let min_x = (((position.x - radius) / cell_size.x).ceil().max(0.0) as usize + 1).min(cells_x + 1);
+            let max_x = (((position.x + radius) / cell_size.x).floor().max(0.0) as usize + 1).min(cells_x + 1);
+            let min_y = (((position.y - radius) / cell_size.y).ceil().max(0.0) as usize + 1).min(cells_y + 1);
+            let max_y = (((position.y + radius) / cell_size.y).floor().max(0.0) as usize + 1).min(cells_y + 1);
+
+            for y in min_y..=max_y {
+                for x in min_x..=max_x {
+                    let node = Vector2::new((x as f32 - 1.0) * cell_size.x, (y as f32 - 1.0) * cell_size.y);
+                    values[y * nodes_x + x] += self.particles.mass * self.smoothing_kernel(position.distance_to(node));
+                }
+            }
+        }
+
+        DensityGrid { nodes_x, nodes_y, cell_size, values }
+    }
+
+    /// Extracts the oriented segments of the iso-contour in every cell, as pairs of (edge id, point).
+    /// The liquid is always on the same side of a segment, which lets them be chained into closed contours.
+    fn march_squares(grid: &DensityGrid, iso_value: f32) -> Vec<((usize, Vector2), (usize, Vector2))> {
+        let mut segments = Vec::new();
+
+        for y in 0..grid.nodes_y - 1 {
+            for x in 0..grid.nodes_x - 1 {
+                // Corners clockwise from the top left, edge k going from corner k to corner k + 1
+                let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+                let values = corners.map(|(cx, cy)| grid.value(cx, cy));
+                let inside = values.map(|value| value >= iso_value);
+                let edges = [grid.horizontal_edge(x, y), grid.vertical_edge(x + 1, y), grid.horizontal_edge(x, y + 1), grid.vertical_edge(x, y)];
+
+                let crossing = |edge: usize| {
+                    let (a, b) = (edge, (edge + 1) % 4);
+                    let t = ((iso_value - values[a]) / (values[b] - values[a])).clamp(0.0, 1.0);
+                    let start = grid.node_position(corners[a].0, corners[a].1);
+                    let end = grid.node_position(corners[b].0, corners[b].1);
+                    (edges[edge], start + (end - start) * t)
+                };
+
+                let inside_count = inside.iter().filter(|corner| **corner).count();
+                if inside_count == 0 || inside_count == 4 {
+                    continue;
+                }
+
+                // Saddle: the average of the corners decides whether the liquid corners are connected
+                let saddle = inside_count == 2 && inside[0] == inside[2];
+                let center_inside = values.iter().sum::<f32>() / 4.0 >= iso_value;
+
+                for k in 0..4 {
+                    let previous = (k + 3) % 4;
+                    if saddle && center_inside {
+                        // The liquid corners are connected, cut off each empty corner
+                        if !inside[k] {
+                            segments.push((crossing(k), crossing(previous)));
+                        }
+                    } else if saddle {
+                        // The liquid corners are separated, cut off each of them
+                        if inside[k] {
+                            segments.push((crossing(previous), crossing(k)));
+                        }
+                    } else if inside[k] && !inside[previous] {
+                        // The single run of liquid corners, starting at corner k
+                        let mut last = k;
+                        while inside[(last + 1) % 4] {
+                            last = (last + 1) % 4;
+                        }
+                        segments.push((crossing(previous), crossing(last)));
+                    }
+                }
+            }
+        }
+
+        segments
+    }
+
+    /// Samples the density onto a grid and extracts the liquid surface as closed contours.
+    pub fn extract_surface(&self) -> FluidSurface {
+        let grid = self.sample_density_grid();
+        // The iso level is relative to the average density of the particles rather than the target density,
+        // which the explicit solver only reaches under high stiffness
+        let average_density = self.particles.densities.iter().sum::<f32>() / self.particles.len().max(1) as f32;
+        let iso_value = self.surface_iso_level * average_density;
+        let segments = Self::march_squares(&grid, iso_value);
+
+        let is_inside_box = |point: Vector2| {
+            let tolerance = 1e-3;
+            point.x >= -tolerance && point.y >= -tolerance && point.x <= self.box_bound_x + tolerance && point.y <= self.box_bound_y + tolerance
+        };
+
+        let mut surface = FluidSurface::default();
+        for ((_, start), (_, end)) in segments.iter() {
+            if is_inside_box(*start) && is_inside_box(*end) {
+                surface.free_surface_length += start.distance_to(*end);
+            }
+        }
+
+        // Chain the segments, each one ending on the edge where the next one starts
+        let starts: HashMap<usize, usize> = segments.iter().enumerate().map(|(i, ((edge, _), _))| (*edge, i)).collect();
+        let mut used = vec![false; segments.len()];
+        let mut signed_area = 0.0;
+
+        for first in 0..segments.len() {
+            if used[first] {
+                continue;
+            }
+
+            let mut points = Vec::new();
+            let mut current = first;
+            while !used[current] {
+                used[current] = true;
+                let ((_, start), (end_edge, _)) = segments[current];
+                points.push(start);
+                match starts.get(&end_edge) {
+                    Some(next) => current = *next,
+                    None => break,
+                }
+            }
+
+            // Shoelace formula, the holes having the opposite orientation
+            let contour_area = (0..points.len()).map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                a.x * b.y - b.x * a.y
+            }).sum::<f32>() / 2.0;
+            signed_area += contour_area;
+
+            surface.contours.push(SurfaceContour { points, hole: contour_area > 0.0 });
+        }
+
+        surface.area = signed_area.abs();
+        surface
+    }
+
+    /// Extracts the surface when the surface layer is shown, to be called once the physics of the frame is done.
+    pub fn update_surface(&mut self) {
+        self.surface = if self.show_surface {
+            Some(self.extract_surface())
+        } else {
+            None
+        };
+    }
+}
@@ -4,12 +4,17 @@ use crate::core::sciences::maths::vector2::Vector2;
 
 use super::main::Fluid;
 use super::data::*;
+use super::obstacle::Obstacle;
+use super::emitter::{Emitter, Sink};
+use super::thermal::ThermalRegion;
+use crate::core::sciences::physics::force_fields::handle_force_field_event;
 
 /// Obligatory implementation of the `SimulationTemplate` trait for the `BouncingBallSimulation` struct.
 impl SimulationTemplate for Fluid {
     fn initialize(&mut self, renderer_size: Vector2, serialized_data: Option<String>) -> Result<(), String> {
         self.box_bound_x = renderer_size.x;
         self.box_bound_y = renderer_size.y;
+        self.particles.domain_size = renderer_size;
 
         let starter_data: StarterData = match serialized_data {
             Some(data) => match serde_json::from_str(&data) {
@@ -19,22 +24,49 @@ impl SimulationTemplate for Fluid {
             None => return Ok(())
         };
 
-        for position in starter_data.positions {
+        let mut positions = starter_data.positions;
+        for layout in starter_data.layouts.iter() {
+            positions.extend(layout.generate(renderer_size, self.max_particles.saturating_sub(positions.len()))?);
+        }
+
+        if positions.len() > self.max_particles {
+            return Err(format!("The initial configuration has {} particles, more than the maximum of {}", positions.len(), self.max_particles));
+        }
+
+        for position in positions {
             self.particles.push(position);
         }
 
+        self.obstacles = starter_data.obstacles;
+        for emitter in starter_data.emitters.iter() {
+            emitter.check_rate()?;
+        }
+        self.emitters = starter_data.emitters;
+        self.sinks = starter_data.sinks;
+        self.thermal_regions = starter_data.thermal_regions;
+        self.sample_boundary_particles();
+
         Ok(())
     }
 
     fn next_step(&mut self, dt: f32) -> Result<(), String> {
-        self.update(dt);
+        self.step(dt);
 
         Ok(())
     }
 
     fn get_data_to_render(&self) -> Result<Box<dyn DataToSend>, String> {
         let data_to_render = RendererData {
-            fluid_particles: self.particles.clone() // Use the clone method is not the most efficient way to do this, but it is the simplest way to implement it for now.
+            fluid_particles: self.particles.clone(), // Use the clone method is not the most efficient way to do this, but it is the simplest way to implement it for now.
+            obstacles: self.obstacles.clone(),
+            emitters: self.emitters.clone(),
+            sinks: self.sinks.clone(),
+            thermal_regions: self.thermal_regions.clone(),
+            metrics: self.metrics,
+            substeps: self.substeps,
+            displayed_range: self.displayed_range,
+            surface: self.surface.clone(),
+            force_fields: self.force_fields.clone()
         };
         
         Ok(Box::new(data_to_render))
@@ -52,21 +84,43 @@ impl SimulationTemplate for Fluid {
 
                         self.collision_restitution = settings.collision_restitution;
                         self.gravity = settings.gravity;
+                        self.integrator = settings.integrator;
                         self.particles.target_density = settings.target_density;
                         self.particles.pressure_multiplier = settings.pressure_stiffness;
                         self.visual_filter = settings.visual_filter;
+                        self.color_range = settings.color_range;
+                        self.show_surface = settings.show_surface;
+                        self.surface_resolution = settings.surface_resolution.max(1.0);
+                        self.surface_iso_level = settings.surface_iso_level;
                         self.particles.smoothing_radius = settings.smoothing_radius;
                         self.viscosity_strength = settings.viscosity_strength;
                         self.interactive_force_mode = settings.interactive_force_mode;
+                        self.surface_tension_strength = settings.surface_tension_strength;
+                        self.adhesion_strength = settings.adhesion_strength;
+                        self.use_boundary_particles = settings.use_boundary_particles;
+                        self.particles.periodic = settings.periodic;
+                        self.solver = settings.solver;
+                        self.solver_iterations = settings.solver_iterations;
+                        self.kernels = settings.kernels;
+                        self.max_density_error = settings.max_density_error;
+                        self.thermal_diffusivity = settings.thermal_diffusivity;
+                        self.thermal_expansion = settings.thermal_expansion;
+                        self.viscoelastic = settings.viscoelastic;
+                        self.spring_stiffness = settings.spring_stiffness;
+                        self.yield_ratio = settings.yield_ratio;
+                        self.plasticity = settings.plasticity;
+                        self.particles.ambient_temperature = settings.ambient_temperature;
+
+                        if !self.viscoelastic {
+                            self.springs.clear();
+                        }
                     },
                     None => return Err("No data provided for the 'set_settings' event".to_string())
                 }
 
-                if self.visual_filter == 0 {
-                    for color in self.particles.colors.iter_mut() {
-                        *color = "#FFFFFFFF".to_string();
-                    }
-                }
+                self.sample_boundary_particles();
+                self.update_colors();
+                self.update_surface();
             },
             "interractive_force_toggle" => {
                 match data {
@@ -95,6 +149,145 @@ impl SimulationTemplate for Fluid {
                     None => return Err("No data provided for the 'interactive_force' event".to_string())
                 }
             },
+            "add_obstacle" => {
+                match data {
+                    Some(data) => {
+                        let obstacle: Obstacle = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.obstacles.push(obstacle);
+                        self.sample_boundary_particles();
+                    },
+                    None => return Err("No data provided for the 'add_obstacle' event".to_string())
+                }
+            },
+            "remove_obstacle" => {
+                match data {
+                    Some(data) => {
+                        let index: usize = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        if index >= self.obstacles.len() {
+                            return Err(format!("No obstacle at index {}", index));
+                        }
+
+                        self.obstacles.remove(index);
+                        self.sample_boundary_particles();
+                    },
+                    None => return Err("No data provided for the 'remove_obstacle' event".to_string())
+                }
+            },
+            "clear_obstacles" => {
+                self.obstacles.clear();
+                self.sample_boundary_particles();
+            },
+            "add_emitter" => {
+                match data {
+                    Some(data) => {
+                        let emitter: Emitter = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        emitter.check_rate()?;
+                        self.emitters.push(emitter);
+                    },
+                    None => return Err("No data provided for the 'add_emitter' event".to_string())
+                }
+            },
+            "remove_emitter" => {
+                match data {
+                    Some(data) => {
+                        let index: usize = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        if index >= self.emitters.len() {
+                            return Err(format!("No emitter at index {}", index));
+                        }
+
+                        self.emitters.remove(index);
+                    },
+                    None => return Err("No data provided for the 'remove_emitter' event".to_string())
+                }
+            },
+            "clear_emitters" => {
+                self.emitters.clear();
+            },
+            "add_sink" => {
+                match data {
+                    Some(data) => {
+                        let sink: Sink = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.sinks.push(sink);
+                    },
+                    None => return Err("No data provided for the 'add_sink' event".to_string())
+                }
+            },
+            "remove_sink" => {
+                match data {
+                    Some(data) => {
+                        let index: usize = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        if index >= self.sinks.len() {
+                            return Err(format!("No sink at index {}", index));
+                        }
+
+                        self.sinks.remove(index);
+                    },
+                    None => return Err("No data provided for the 'remove_sink' event".to_string())
+                }
+            },
+            "clear_sinks" => {
+                self.sinks.clear();
+            },
+            "add_thermal_region" => {
+                match data {
+                    Some(data) => {
+                        let region: ThermalRegion = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.thermal_regions.push(region);
+                    },
+                    None => return Err("No data provided for the 'add_thermal_region' event".to_string())
+                }
+            },
+            "remove_thermal_region" => {
+                match data {
+                    Some(data) => {
+                        let index: usize = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        if index >= self.thermal_regions.len() {
+                            return Err(format!("No thermal region at index {}", index));
+                        }
+
+                        self.thermal_regions.remove(index);
+                    },
+                    None => return Err("No data provided for the 'remove_thermal_region' event".to_string())
+                }
+            },
+            "clear_thermal_regions" => {
+                self.thermal_regions.clear();
+            },
+            "add_force_field" | "remove_force_field" | "clear_force_fields" => {
+                handle_force_field_event(&mut self.force_fields, &event, data)?;
+            },
             _ => return Err("Unknown event".to_string())
         }
 
@@ -0,0 +1,87 @@
+use serde::{Serialize, Deserialize};
+
+use crate::core::sciences::maths::Vector2;
+
+use super::main::Fluid;
+use super::obstacle::ObstacleShape;
+
+/// A heated or cooled region, usually a band along a wall, which drives the temperature
+/// of the particles inside it towards its own temperature.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ThermalRegion {
+    pub shape: ObstacleShape,
+    pub temperature: f32,
+    /// Fraction of the temperature difference exchanged per second.
+    pub transfer_rate: f32,
+}
+
+impl ThermalRegion {
+    pub fn new(shape: ObstacleShape, temperature: f32, transfer_rate: f32) -> Self {
+        Self {
+            shape,
+            temperature,
+            transfer_rate
+        }
+    }
+
+    pub fn contains(&self, position: Vector2) -> bool {
+        self.shape.signed_distance(position).0 < 0.0
+    }
+}
+
+/// Heat transfer and Boussinesq buoyancy.
+impl Fluid {
+    /// Computes the rate of change of the temperature of particle `i` by SPH heat diffusion,
+    /// with the Laplacian approximation of Cleary & Monaghan (1999).
+    fn calculate_heat_diffusion(&self, i: usize) -> f32 {
+        let position = self.particles.predicted_positions[i];
+        let temperature = self.particles.temperatures[i];
+        // Avoids the singularity of the Laplacian when two particles overlap
+        let epsilon = 0.01 * self.particles.smoothing_radius * self.particles.smoothing_radius;
+        let mut laplacian = 0.0;
+
+        for other_i in self.in_radius_neighbors_search(i) {
+            let density = self.particles.densities[other_i];
+            if density <= f32::EPSILON {
+                continue;
+            }
+
+            let offset = self.particles.offset(position, self.particles.predicted_positions[other_i]);
+            let distance = offset.magnitude();
+            let slope = self.smoothing_kernel_derivative(distance);
+
+            laplacian += 2.0 * self.particles.mass / density * (temperature - self.particles.temperatures[other_i]) * distance * slope / (distance * distance + epsilon);
+        }
+
+        laplacian * self.thermal_diffusivity
+    }
+
+    /// Diffuses the heat between neighbours, exchanges heat with the thermal regions,
+    /// then applies the Boussinesq buoyancy which makes the particles warmer than
+    /// `ambient_temperature` rise. Must be called once the densities are known.
+    pub(super) fn apply_heat_transfer(&mut self, dt: f32) {
+        if self.thermal_diffusivity > 0.0 {
+            let heat_rates: Vec<f32> = (0..self.particles.len()).map(|i| self.calculate_heat_diffusion(i)).collect();
+            (0..self.particles.len()).for_each(|i| {
+                self.particles.temperatures[i] += heat_rates[i] * dt;
+            });
+        }
+
+        for region in self.thermal_regions.iter() {
+            // Implicit relaxation, stable for any transfer rate
+            let exchange = 1.0 - (-region.transfer_rate * dt).exp();
+            (0..self.particles.len()).for_each(|i| {
+                if region.contains(self.particles.positions[i]) {
+                    self.particles.temperatures[i] += (region.temperature - self.particles.temperatures[i]) * exchange;
+                }
+            });
+        }
+
+        if self.thermal_expansion > 0.0 {
+            (0..self.particles.len()).for_each(|i| {
+                let temperature_difference = self.particles.temperatures[i] - self.particles.ambient_temperature;
+                self.particles.velocities[i] += Vector2::up() * self.gravity * self.thermal_expansion * temperature_difference * dt;
+            });
+        }
+    }
+}
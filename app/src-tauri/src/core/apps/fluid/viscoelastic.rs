@@ -0,0 +1,84 @@
+use crate::core::sciences::maths::Vector2;
+
+use super::main::Fluid;
+
+/// Viscoelasticity and plasticity with dynamic springs between neighbours (Clavet et al. 2005).
+///
+/// A spring is created between two particles as soon as they are neighbours, with their distance as rest length.
+/// The rest length follows the deformation once it exceeds `yield_ratio` times the rest length, at a rate set by
+/// `plasticity`, and the spring breaks when its rest length exceeds the smoothing radius.
+impl Fluid {
+    /// Creates the springs between new neighbours, then stretches, compresses and breaks the existing ones.
+    fn update_springs(&mut self, dt: f32) {
+        let h = self.particles.smoothing_radius;
+
+        for i in 0..self.particles.len() {
+            for j in self.in_radius_neighbors_search(i) {
+                if j <= i {
+                    continue;
+                }
+
+                let distance = self.particles.distance(self.particles.predicted_positions[i], self.particles.predicted_positions[j]);
+                let rest_length = self.springs.entry((i, j)).or_insert(distance);
+                let tolerable_deformation = self.yield_ratio * *rest_length;
+
+                if distance > *rest_length + tolerable_deformation {
+                    *rest_length += dt * self.plasticity * (distance - *rest_length - tolerable_deformation);
+                } else if distance < *rest_length - tolerable_deformation {
+                    *rest_length -= dt * self.plasticity * (*rest_length - tolerable_deformation - distance);
+                }
+            }
+        }
+
+        self.springs.retain(|_, rest_length| *rest_length < h);
+    }
+
+    /// Applies the spring forces to the velocities and the predicted positions.
+    pub(super) fn apply_springs(&mut self, dt: f32) {
+        if !self.viscoelastic {
+            return;
+        }
+
+        self.update_springs(dt);
+
+        let h = self.particles.smoothing_radius;
+        let mut velocity_changes = vec![Vector2::zero(); self.particles.len()];
+
+        for ((i, j), rest_length) in self.springs.iter() {
+            let offset = self.particles.offset(self.particles.predicted_positions[*j], self.particles.predicted_positions[*i]);
+            let distance = offset.magnitude();
+            if distance <= f32::EPSILON {
+                continue;
+            }
+
+            // Displacement of Clavet et al., shared between the two particles and turned into a velocity change
+            let displacement = offset / distance * (dt * dt * self.spring_stiffness * (1.0 - rest_length / h) * (rest_length - distance));
+            velocity_changes[*i] -= displacement / (2.0 * dt);
+            velocity_changes[*j] += displacement / (2.0 * dt);
+        }
+
+        (0..self.particles.len()).for_each(|i| {
+            self.particles.velocities[i] += velocity_changes[i];
+            self.particles.predicted_positions[i] += velocity_changes[i] * dt;
+        });
+    }
+
+    /// Keeps the springs attached to the right particles after `FluidParticles::swap_remove(i)`,
+    /// which moves the last particle to index `i`.
+    pub(super) fn remap_springs_after_removal(&mut self, i: usize) {
+        if self.springs.is_empty() {
+            return;
+        }
+
+        let last = self.particles.len();
+        let springs = std::mem::take(&mut self.springs);
+        self.springs = springs.into_iter()
+            .filter(|((a, b), _)| *a != i && *b != i)
+            .map(|((a, b), rest_length)| {
+                let a = if a == last { i } else { a };
+                let b = if b == last { i } else { b };
+                ((a.min(b), a.max(b)), rest_length)
+            })
+            .collect();
+    }
+}
@@ -0,0 +1,114 @@
+use serde::{Serialize, Deserialize};
+
+use super::main::Fluid;
+
+/// The quantity shown by the colour of the particles.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum VisualFilter {
+    /// Every particle is white.
+    None,
+    Speed,
+    /// Pressure given by the solver: the equation of state, the constraint multipliers or the solved pressure.
+    Pressure,
+    Density,
+    /// Curl of the velocity field, positive for counter-clockwise rotation on screen.
+    Vorticity,
+    NeighbourCount,
+    Phase,
+    Temperature,
+}
+
+/// How the values of the filter are mapped on the colour gradient.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ColorRange {
+    /// The range follows the smallest and largest values of each frame.
+    Auto,
+    Fixed { min: f32, max: f32 },
+}
+
+/// Returns the range of `values` mapped on the gradient, following `color_range`.
+///
+/// In automatic mode, `symmetric` centers the range on zero for signed quantities.
+pub fn displayed_range(color_range: ColorRange, values: &[f32], symmetric: bool) -> (f32, f32) {
+    match color_range {
+        ColorRange::Fixed { min, max } => (min, max),
+        ColorRange::Auto => {
+            let (min, max) = values.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| (min.min(*value), max.max(*value)));
+            if min > max {
+                return (0.0, 1.0);
+            }
+
+            if symmetric {
+                let extent = min.abs().max(max.abs());
+                return (-extent, extent);
+            }
+
+            (min, max)
+        }
+    }
+}
+
+/// Visualization stage, colouring the particles from the state left by the solvers.
+impl Fluid {
+    /// Computes the vorticity of particle `i`.
+    fn calculate_vorticity(&self, i: usize, neighbors: &[usize]) -> f32 {
+        let position = self.particles.predicted_positions[i];
+        let velocity = self.particles.velocities[i];
+        let mut vorticity = 0.0;
+
+        for other_i in neighbors {
+            let density = self.particles.densities[*other_i];
+            if density <= f32::EPSILON {
+                continue;
+            }
+
+            let gradient = self.kernel_gradient(self.particles.offset(position, self.particles.predicted_positions[*other_i]));
+            vorticity += (self.particles.velocities[*other_i] - velocity).cross(gradient) * self.particles.mass / density;
+        }
+
+        vorticity
+    }
+
+    /// Returns the value shown by the visual filter for every particle.
+    pub fn visual_values(&self) -> Vec<f32> {
+        let particle_count = self.particles.len();
+
+        match self.visual_filter {
+            VisualFilter::None => vec![0.0; particle_count],
+            VisualFilter::Speed => self.particles.velocities.iter().map(|velocity| velocity.magnitude()).collect(),
+            VisualFilter::Pressure => self.particles.pressures.clone(),
+            VisualFilter::Density => self.particles.densities.clone(),
+            VisualFilter::Vorticity => (0..particle_count).map(|i| self.calculate_vorticity(i, &self.in_radius_neighbors_search(i))).collect(),
+            VisualFilter::NeighbourCount => (0..particle_count).map(|i| self.in_radius_neighbors_search(i).len() as f32).collect(),
+            VisualFilter::Phase => self.particles.phases.iter().map(|phase| *phase as f32).collect(),
+            VisualFilter::Temperature => self.particles.temperatures.clone(),
+        }
+    }
+
+    /// Colours the particles with the visual filter, to be called once the physics of the frame is done.
+    pub fn update_colors(&mut self) {
+        if self.visual_filter == VisualFilter::None {
+            for color in self.particles.colors.iter_mut() {
+                *color = "#FFFFFFFF".to_string();
+            }
+            return;
+        }
+
+        let values = self.visual_values();
+        let (min, max) = displayed_range(self.color_range, &values, self.visual_filter == VisualFilter::Vorticity);
+        let span = (max - min).max(f32::EPSILON);
+        self.displayed_range = (min, max);
+
+        let gradient = match self.visual_filter {
+            VisualFilter::Pressure | VisualFilter::Density | VisualFilter::Vorticity => &self.density_gradient,
+            VisualFilter::Temperature => &self.temperature_gradient,
+            _ => &self.velocity_gradient,
+        };
+
+        for (color, value) in self.particles.colors.iter_mut().zip(values.iter()) {
+            *color = gradient.at(((value - min) / span) as f64).to_hex_string();
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Serialize, Deserialize};
+
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::apps::fluid::main::FluidParticles;
+use crate::core::apps::fluid::surface::FluidSurface;
+use crate::core::apps::rigibody::data::LightRigidBody;
+use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::physics::force_fields::ForceField;
+use crate::core::sciences::physics::rigid_body::r#struct::ShapeType;
+
+/// Bodies are sent with their center as position, unlike the rigid body template.
+#[derive(Serialize, Clone)]
+pub struct RendererData {
+    pub fluid_particles: FluidParticles,
+    pub bodies: Vec<LightRigidBody>,
+    pub substeps: usize,
+    /// Surface of the fluid, `None` unless it is shown.
+    pub surface: Option<FluidSurface>,
+    pub force_fields: Vec<ForceField>,
+}
+
+impl DataToSend for RendererData {}
+
+/// Description of a rigid body to add to the scene, `position` being its center.
+#[derive(Deserialize, Clone)]
+pub struct BodyDescription {
+    pub shape: ShapeType,
+    pub position: Vector2,
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    pub radius: f32,
+    #[serde(default)]
+    pub width: f32,
+    #[serde(default)]
+    pub height: f32,
+    pub density: f32,
+    pub restitution: f32,
+    #[serde(default)]
+    pub is_static: bool
+}
+
+/// Starter data of the scene, the fluid part of it (positions, obstacles...) is read by the fluid itself.
+#[derive(Deserialize, Clone)]
+pub struct StarterData {
+    #[serde(default)]
+    pub bodies: Vec<BodyDescription>
+}
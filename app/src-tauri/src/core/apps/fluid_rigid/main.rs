@@ -0,0 +1,289 @@
+use colorgrad::Gradient;
+
+use crate::core::apps::fluid::main::Fluid;
+use crate::core::apps::fluid::moving_boundary::MovingBoundary;
+use crate::core::apps::fluid::obstacle::ObstacleShape;
+use crate::core::sciences::maths::Vector2;
+use crate::core::sciences::physics::force_fields::total_acceleration;
+use crate::core::sciences::physics::rigid_body::flatrgb::initializer_r;
+use crate::core::sciences::physics::rigid_body::r#struct::{RigidBody, ShapeType, WorkSpace};
+use crate::core::sciences::physics::rigid_body::vectormath::c_vect;
+
+use super::data::BodyDescription;
+
+/// Number of passes over the contacts between bodies at each substep, letting the stacks settle.
+const CONTACT_ITERATIONS: usize = 4;
+
+/// A contact between two bodies: the unit normal from the first body to the second, the penetration depth and the contact point.
+struct BodyContact {
+    normal: Vector2,
+    depth: f32,
+    point: Vector2,
+}
+
+/// Represents a scene in which rigid bodies float, sink and get pushed in an SPH fluid.
+///
+/// The bodies are stored in a `WorkSpace` and are seen by the fluid as moving boundaries.
+/// At each step the fluid computes its pressure and collision forces on every body,
+/// which are then integrated here as a force and a torque, together with the gravity,
+/// before the bodies collide with each other.
+pub struct FluidRigidSimulation {
+    pub fluid: Fluid,
+    pub work_space: WorkSpace,
+    pub body_friction: f32,
+}
+
+impl FluidRigidSimulation {
+    /// Creates a new `FluidRigidSimulation` instance without any body.
+    ///
+    /// # Arguments
+    ///
+    /// * `velocity_gradient` - The gradient used by the fluid to colour the particles.
+    ///
+    /// # Returns
+    ///
+    /// A new `FluidRigidSimulation` instance.
+    pub fn new(velocity_gradient: Gradient) -> Self {
+        FluidRigidSimulation {
+            fluid: Fluid::new(velocity_gradient),
+            work_space: WorkSpace::new(),
+            body_friction: 0.1,
+        }
+    }
+
+    /// Creates a rigid body from its description and adds it to the scene.
+    ///
+    /// The mass and the rotational inertia are computed from the density and the area of the shape.
+    pub fn add_body(&mut self, description: &BodyDescription) {
+        let radius = description.radius as f64;
+        let width = description.width as f64;
+        let height = description.height as f64;
+        let density = description.density as f64;
+
+        let (area, inertia_factor) = match description.shape {
+            ShapeType::Circle => (std::f64::consts::PI * radius * radius, radius * radius / 2.0),
+            ShapeType::Box => (width * height, (width * width + height * height) / 12.0),
+        };
+        let mass = density * area;
+
+        let mut body = initializer_r(density, mass, description.restitution as f64, area, description.is_static,
+            radius, width, height, description.shape, mass * inertia_factor);
+        body.position = c_vect(description.position.x as f64, description.position.y as f64);
+        body.angle = description.rotation as f64;
+
+        self.work_space.add_body(body);
+    }
+
+    /// Removes every body of the scene.
+    pub fn clear_bodies(&mut self) {
+        self.work_space.body_list.clear();
+        self.work_space.body_count = 0;
+    }
+
+    /// Returns the geometry of a body as seen by the fluid.
+    pub fn body_shape(body: &RigidBody) -> ObstacleShape {
+        let center = Vector2::new(body.position.x as f32, body.position.y as f32);
+        match body.shape {
+            ShapeType::Circle => ObstacleShape::Circle { center, radius: body.radius as f32 },
+            ShapeType::Box => ObstacleShape::Rectangle {
+                center,
+                size: Vector2::new(body.width as f32, body.height as f32),
+                rotation: body.angle as f32
+            },
+        }
+    }
+
+    /// Replaces the moving boundaries of the fluid by the current state of the bodies.
+    fn sync_moving_boundaries(&mut self) {
+        self.fluid.moving_boundaries = self.work_space.body_list.iter().map(|body| {
+            MovingBoundary::new(
+                Self::body_shape(body),
+                Vector2::new(body.position.x as f32, body.position.y as f32),
+                Vector2::new(body.linear_velocity.x as f32, body.linear_velocity.y as f32),
+                body.angular_velocity as f32,
+                body.restitution as f32,
+                self.body_friction,
+            )
+        }).collect();
+    }
+
+    /// Integrates the bodies with the gravity, the force fields and the force and torque exerted by the fluid,
+    /// using the integrator of the work space, then keeps them inside the fluid box.
+    ///
+    /// The rotation is integrated with semi-implicit Euler, the torque of the fluid being the only one.
+    fn integrate_bodies(&mut self, dt: f32) {
+        let gravity = Vector2::down() * self.fluid.gravity;
+        let (box_bound_x, box_bound_y) = (self.fluid.box_bound_x as f64, self.fluid.box_bound_y as f64);
+        let integrator = self.work_space.integrator;
+        let force_fields = &self.fluid.force_fields;
+
+        for (body, moving_boundary) in self.work_space.body_list.iter_mut().zip(self.fluid.moving_boundaries.iter()) {
+            if body.is_static || body.mass <= 0.0 {
+                continue;
+            }
+
+            if body.inertia > 0.0 {
+                body.angular_velocity += (moving_boundary.torque * dt) as f64 / body.inertia;
+            }
+
+            // The fluid force is accumulated as the force of the body, added by `step_body` to the acceleration
+            body.force = c_vect(moving_boundary.force.x as f64, moving_boundary.force.y as f64);
+            body.step_body(dt as f64, 1, integrator, |position, velocity| gravity + total_acceleration(force_fields, position, velocity));
+
+            // Half extents of the axis-aligned bounding box of the body
+            let (extent_x, extent_y) = match body.shape {
+                ShapeType::Circle => (body.radius, body.radius),
+                ShapeType::Box => {
+                    let (sin, cos) = (body.angle.sin().abs(), body.angle.cos().abs());
+                    ((body.width * cos + body.height * sin) / 2.0, (body.width * sin + body.height * cos) / 2.0)
+                },
+            };
+
+            if body.position.x - extent_x < 0.0 {
+                body.position.x = extent_x;
+                body.linear_velocity.x = -body.linear_velocity.x * body.restitution;
+            } else if body.position.x + extent_x > box_bound_x {
+                body.position.x = box_bound_x - extent_x;
+                body.linear_velocity.x = -body.linear_velocity.x * body.restitution;
+            }
+
+            if body.position.y - extent_y < 0.0 {
+                body.position.y = extent_y;
+                body.linear_velocity.y = -body.linear_velocity.y * body.restitution;
+            } else if body.position.y + extent_y > box_bound_y {
+                body.position.y = box_bound_y - extent_y;
+                body.linear_velocity.y = -body.linear_velocity.y * body.restitution;
+            }
+        }
+    }
+
+    /// Finds the contact between bodies `a` and `b`, if they overlap, from the shapes seen by the fluid.
+    ///
+    /// A circle touches a shape where its center is closer to it than its radius. Two boxes are separated
+    /// along the face normal of least overlap, and touch at the corners lying the deepest along it.
+    fn find_contact(a: &RigidBody, b: &RigidBody) -> Option<BodyContact> {
+        let (shape_a, shape_b) = (Self::body_shape(a), Self::body_shape(b));
+
+        match (&shape_a, &shape_b) {
+            (_, ObstacleShape::Circle { center, radius }) => {
+                let (distance, normal) = shape_a.signed_distance(*center);
+                (distance < *radius).then(|| BodyContact { normal, depth: radius - distance, point: *center - normal * *radius })
+            },
+            (ObstacleShape::Circle { .. }, _) => Self::find_contact(b, a).map(|contact| BodyContact { normal: contact.normal * -1.0, ..contact }),
+            _ => Self::find_box_contact(&Self::box_corners(&shape_a), &Self::box_corners(&shape_b)),
+        }
+    }
+
+    /// Finds the contact between two boxes given by their corners, with the separating axis theorem.
+    fn find_box_contact(corners_a: &[Vector2], corners_b: &[Vector2]) -> Option<BodyContact> {
+        let project = |corners: &[Vector2], axis: Vector2| corners.iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), corner| (min.min(*corner * axis), max.max(*corner * axis)));
+        let face_normals = |corners: &[Vector2]| [corners[1] - corners[0], corners[2] - corners[1]]
+            .map(|edge| edge.perpendicular().normalize().unwrap_or(Vector2::zero()));
+
+        // Axis of least overlap, oriented from `a` to `b`, and whether it is a face normal of `a`
+        let mut best: Option<(Vector2, f32, bool)> = None;
+        for (axis, of_a) in face_normals(corners_a).into_iter().map(|axis| (axis, true)).chain(face_normals(corners_b).into_iter().map(|axis| (axis, false))) {
+            let ((min_a, max_a), (min_b, max_b)) = (project(corners_a, axis), project(corners_b, axis));
+            let overlap = (max_a - min_b).min(max_b - min_a);
+            if overlap <= 0.0 {
+                return None;
+            }
+
+            if !matches!(best, Some((_, depth, _)) if depth <= overlap) {
+                let normal = if max_b - min_a < max_a - min_b { axis * -1.0 } else { axis };
+                best = Some((normal, overlap, of_a));
+            }
+        }
+
+        // The contact point is the corner of the other box deepest along the normal, or the middle of its deepest edge
+        let (normal, depth, of_a) = best?;
+        let (incident, direction) = if of_a { (corners_b, normal * -1.0) } else { (corners_a, normal) };
+        let deepest = incident.iter().map(|corner| *corner * direction).fold(f32::NEG_INFINITY, f32::max);
+        let touching: Vec<Vector2> = incident.iter().copied().filter(|corner| *corner * direction > deepest - 0.5).collect();
+        let point = touching.iter().fold(Vector2::zero(), |sum, corner| sum + *corner) / touching.len() as f32;
+
+        Some(BodyContact { normal, depth, point })
+    }
+
+    /// Returns the corners of a rectangle in order around it, none for the other shapes.
+    fn box_corners(shape: &ObstacleShape) -> Vec<Vector2> {
+        match shape {
+            ObstacleShape::Rectangle { center, size, rotation } => [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].iter()
+                .map(|(x, y)| *center + Vector2::new(x * size.x, y * size.y).rotate(*rotation) / 2.0)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Separates the overlapping bodies in proportion to their inverse masses, and removes the approaching
+    /// velocity at the contact point but the part given back by the lowest restitution of the two.
+    ///
+    /// The collision step of the `WorkSpace` is not used, its bodies being stepped here with the fluid forces.
+    fn resolve_body_collisions(&mut self) {
+        let bodies = &mut self.work_space.body_list;
+        let inverse_mass = |body: &RigidBody| if body.is_static || body.mass <= 0.0 { 0.0 } else { 1.0 / body.mass as f32 };
+        let inverse_inertia = |body: &RigidBody| if body.is_static || body.inertia <= 0.0 { 0.0 } else { 1.0 / body.inertia as f32 };
+
+        for _ in 0..CONTACT_ITERATIONS {
+            for i in 0..bodies.len() {
+                for j in i + 1..bodies.len() {
+                    let (inverse_mass_a, inverse_mass_b) = (inverse_mass(&bodies[i]), inverse_mass(&bodies[j]));
+                    let total_inverse_mass = inverse_mass_a + inverse_mass_b;
+                    if total_inverse_mass <= 0.0 {
+                        continue;
+                    }
+
+                    let contact = match Self::find_contact(&bodies[i], &bodies[j]) {
+                        Some(contact) => contact,
+                        None => continue,
+                    };
+
+                    // Separate the bodies along the normal
+                    let correction = contact.normal * (contact.depth / total_inverse_mass);
+                    bodies[i].moves(c_vect((-correction.x * inverse_mass_a) as f64, (-correction.y * inverse_mass_a) as f64));
+                    bodies[j].moves(c_vect((correction.x * inverse_mass_b) as f64, (correction.y * inverse_mass_b) as f64));
+
+                    // Impulse removing the approaching velocity at the contact point
+                    let center_a = Vector2::new(bodies[i].position.x as f32, bodies[i].position.y as f32);
+                    let center_b = Vector2::new(bodies[j].position.x as f32, bodies[j].position.y as f32);
+                    let (offset_a, offset_b) = (contact.point - center_a, contact.point - center_b);
+                    let velocity_a = Vector2::new(bodies[i].linear_velocity.x as f32, bodies[i].linear_velocity.y as f32) + offset_a.perpendicular() * bodies[i].angular_velocity as f32;
+                    let velocity_b = Vector2::new(bodies[j].linear_velocity.x as f32, bodies[j].linear_velocity.y as f32) + offset_b.perpendicular() * bodies[j].angular_velocity as f32;
+                    let approach_speed = (velocity_b - velocity_a) * contact.normal;
+                    if approach_speed >= 0.0 {
+                        continue;
+                    }
+
+                    let (inverse_inertia_a, inverse_inertia_b) = (inverse_inertia(&bodies[i]), inverse_inertia(&bodies[j]));
+                    let (arm_a, arm_b) = (offset_a.cross(contact.normal), offset_b.cross(contact.normal));
+                    let restitution = bodies[i].restitution.min(bodies[j].restitution) as f32;
+                    let impulse = -(1.0 + restitution) * approach_speed
+                        / (total_inverse_mass + arm_a * arm_a * inverse_inertia_a + arm_b * arm_b * inverse_inertia_b);
+
+                    let (impulse_a, impulse_b) = (contact.normal * (impulse * inverse_mass_a), contact.normal * (impulse * inverse_mass_b));
+                    bodies[i].linear_velocity = c_vect(bodies[i].linear_velocity.x - impulse_a.x as f64, bodies[i].linear_velocity.y - impulse_a.y as f64);
+                    bodies[j].linear_velocity = c_vect(bodies[j].linear_velocity.x + impulse_b.x as f64, bodies[j].linear_velocity.y + impulse_b.y as f64);
+                    bodies[i].angular_velocity -= (arm_a * impulse * inverse_inertia_a) as f64;
+                    bodies[j].angular_velocity += (arm_b * impulse * inverse_inertia_b) as f64;
+                }
+            }
+        }
+    }
+
+    /// Advances the scene by `dt`, with the substeps chosen by the fluid, the bodies being integrated
+    /// and collided with each other at each one.
+    pub fn update(&mut self, dt: f32) {
+        let substep_dt = self.fluid.plan_substeps(dt);
+
+        for _ in 0..self.fluid.substeps {
+            self.sync_moving_boundaries();
+            self.fluid.update(substep_dt);
+            self.integrate_bodies(substep_dt);
+            self.resolve_body_collisions();
+        }
+
+        self.fluid.update_colors();
+        self.fluid.update_surface();
+    }
+}
@@ -0,0 +1,107 @@
+use crate::core::app_system::simulation::template::SimulationTemplate;
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::apps::rigibody::data::LightRigidBody;
+use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::physics::rigid_body::r#struct::ShapeType;
+
+use super::main::FluidRigidSimulation;
+use super::data::*;
+
+/// Obligatory implementation of the `SimulationTemplate` trait for the `FluidRigidSimulation` struct.
+impl SimulationTemplate for FluidRigidSimulation {
+    fn initialize(&mut self, renderer_size: Vector2, serialized_data: Option<String>) -> Result<(), String> {
+        self.fluid.initialize(renderer_size, serialized_data.clone())?;
+
+        let starter_data: StarterData = match serialized_data {
+            Some(data) => match serde_json::from_str(&data) {
+                Ok(deserialized_data) => deserialized_data,
+                Err(e) => return Err(e.to_string())
+            },
+            None => return Ok(())
+        };
+
+        for description in starter_data.bodies.iter() {
+            self.add_body(description);
+        }
+
+        Ok(())
+    }
+
+    fn next_step(&mut self, dt: f32) -> Result<(), String> {
+        self.update(dt);
+
+        Ok(())
+    }
+
+    fn get_data_to_render(&self) -> Result<Box<dyn DataToSend>, String> {
+        let bodies = self.work_space.body_list.iter().map(|body| LightRigidBody {
+            position: Vector2::new(body.position.x as f32, body.position.y as f32),
+            rotation: body.angle as f32,
+            radius: body.radius as f32,
+            width: body.width as f32,
+            height: body.height as f32,
+            shape: matches!(body.shape, ShapeType::Box)
+        }).collect();
+
+        let data_to_render = RendererData {
+            fluid_particles: self.fluid.particles.clone(),
+            bodies,
+            substeps: self.fluid.substeps,
+            surface: self.fluid.surface.clone(),
+            force_fields: self.fluid.force_fields.clone()
+        };
+
+        Ok(Box::new(data_to_render))
+    }
+
+    fn event_handler(&mut self, event: String, data: Option<String>) -> Result<(), String> {
+        match event.as_str() {
+            "add_body" => {
+                match data {
+                    Some(data) => {
+                        let description: BodyDescription = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.add_body(&description);
+                    },
+                    None => return Err("No data provided for the 'add_body' event".to_string())
+                }
+            },
+            "remove_body" => {
+                match data {
+                    Some(data) => {
+                        let index: usize = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        if !self.work_space.remove_body(index) {
+                            return Err(format!("No body at index {}", index));
+                        }
+                    },
+                    None => return Err("No data provided for the 'remove_body' event".to_string())
+                }
+            },
+            "clear_bodies" => {
+                self.clear_bodies();
+            },
+            "set_integrator" => {
+                match data {
+                    Some(data) => {
+                        self.work_space.integrator = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+                    },
+                    None => return Err("No data provided for the 'set_integrator' event".to_string())
+                }
+            },
+            // Every other event concerns the fluid
+            _ => return self.fluid.event_handler(event, data)
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Serialize, Deserialize};
+
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::physics::force_fields::ForceField;
+use crate::core::sciences::physics::kinetic_theory::SpeedHistogram;
+
+/// The particles of the gas, one entry per particle in each array. All the particles have the same mass and radius.
+#[derive(Serialize, Clone, Default)]
+pub struct Particles {
+    pub positions: Vec<Vector2>,
+    pub velocities: Vec<Vector2>,
+    pub colors: Vec<String>, // store in hex format and not in colorgrad::Color to allow serialization
+}
+
+impl Particles {
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn push(&mut self, position: Vector2, velocity: Vector2, color: String) {
+        self.positions.push(position);
+        self.velocities.push(velocity);
+        self.colors.push(color);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Particles::default();
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct RendererData {
+    pub particles: Particles,
+    pub particle_radius: f32,
+    /// Abscissa of the piston, the right wall of the gas.
+    pub piston_position: f32,
+    /// Area between the left wall and the piston.
+    pub area: f32,
+    pub kinetic_energy: f32,
+    /// Temperature from the mean kinetic energy of the particles.
+    pub temperature: f32,
+    /// Force per unit length on the walls and the piston, averaged over the last measurement window.
+    pub pressure: f32,
+    /// Force per unit length on the piston alone, averaged over the last measurement window.
+    pub piston_pressure: f32,
+    /// `P A / (N k T)`, 1 for an ideal gas and above for a gas of particles taking room.
+    pub compressibility_factor: f32,
+    pub histogram: SpeedHistogram,
+    pub substeps: usize,
+    pub force_fields: Vec<ForceField>,
+}
+
+impl DataToSend for RendererData {}
+
+#[derive(Deserialize, Clone)]
+pub struct StarterData {
+    pub particle_count: usize,
+    pub temperature: f32,
+    pub particle_radius: f32,
+    pub particle_mass: f32,
+}
+
+#[derive(Deserialize)]
+pub struct EventSettings {
+    pub piston_max_speed: f32,
+    /// Duration over which the wall impulses are summed to measure the pressure, in seconds.
+    pub measurement_window: f32,
+    pub histogram_bins: usize,
+}
+
+/// Particles added at random places between the left wall and the piston, with the speeds of a gas at `temperature`.
+#[derive(Deserialize)]
+pub struct EventAddParticles {
+    pub count: usize,
+    pub temperature: f32,
+}
+
+/// Temperature the gas is brought to by scaling the speeds of the particles.
+#[derive(Deserialize)]
+pub struct EventTemperature {
+    pub temperature: f32,
+}
+
+/// Abscissa the piston moves to, at most at its maximum speed.
+#[derive(Deserialize)]
+pub struct EventPiston {
+    pub position: f32,
+}
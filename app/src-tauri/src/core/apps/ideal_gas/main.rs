@@ -0,0 +1,320 @@
+use colorgrad::Gradient;
+use rand::Rng;
+
+use crate::core::sciences::maths::Vector2;
+use crate::core::sciences::physics::force_fields::{ForceField, total_acceleration};
+use crate::core::sciences::physics::kinetic_theory::{SpeedHistogram, most_probable_speed_2d, sample_velocity_2d, temperature_2d};
+
+use super::data::Particles;
+
+/// Gas of small particles colliding elastically, in a box closed on the right by a movable piston.
+///
+/// The pressure is measured as the momentum given to the walls per unit time and length, and the
+/// temperature as the mean kinetic energy of the particles, so that the ideal gas law `P A = N k T`
+/// and the Maxwell–Boltzmann distribution of the speeds can be checked. Moving the piston does work
+/// on the gas, heating it when compressed and cooling it when expanded.
+pub struct IdealGas {
+    // PARTICLE PROPERTIES
+    pub particles: Particles,
+    pub max_particles: usize,
+    pub particle_radius: f32,
+    pub particle_mass: f32,
+    /// Constant relating the temperature to the kinetic energy, chosen to give temperatures around 300 for speeds of a few hundred pixels per second.
+    pub boltzmann_constant: f32,
+    /// External force fields, accelerating the particles between the collisions.
+    pub force_fields: Vec<ForceField>,
+    // TIMESTEP PROPERTIES
+    pub max_substeps: usize,
+    pub substeps: usize,
+    // BOUNDARY PROPERTIES
+    pub box_size: Vector2,
+    pub piston_position: f32,
+    pub piston_target: f32,
+    pub piston_velocity: f32,
+    pub piston_max_speed: f32,
+    // MEASUREMENT PROPERTIES
+    /// Momentum given to all the walls since the start of the measurement window.
+    pub wall_impulse: f32,
+    /// Momentum given to the piston since the start of the measurement window.
+    pub piston_impulse: f32,
+    pub measured_time: f32,
+    pub measurement_window: f32,
+    pub pressure: f32,
+    pub piston_pressure: f32,
+    pub histogram_bins: usize,
+    // RENDER PROPERTIES
+    /// Speed mapped to the end of the color gradient.
+    pub color_speed: f32,
+    pub color_gradient: Gradient
+}
+
+impl IdealGas {
+    pub fn new(color_gradient: Gradient) -> Self {
+        IdealGas {
+            // PARTICLE PROPERTIES
+            particles: Particles::default(),
+            max_particles: 3000,
+            particle_radius: 3.0,
+            particle_mass: 1.0,
+            boltzmann_constant: 100.0,
+            force_fields: Vec::new(),
+            // TIMESTEP PROPERTIES
+            max_substeps: 20,
+            substeps: 1,
+            // BOUNDARY PROPERTIES
+            box_size: Vector2::new(800.0, 600.0),
+            piston_position: 800.0,
+            piston_target: 800.0,
+            piston_velocity: 0.0,
+            piston_max_speed: 200.0,
+            // MEASUREMENT PROPERTIES
+            wall_impulse: 0.0,
+            piston_impulse: 0.0,
+            measured_time: 0.0,
+            measurement_window: 0.5,
+            pressure: 0.0,
+            piston_pressure: 0.0,
+            histogram_bins: 30,
+            // RENDER PROPERTIES
+            color_speed: 600.0,
+            color_gradient
+        }
+    }
+
+    /// Adds `count` particles at random places between the left wall and the piston, avoiding the
+    /// particles already there when possible, with the velocities of a gas at `temperature`.
+    pub fn add_particles(&mut self, count: usize, temperature: f32) -> Result<(), String> {
+        if count > self.max_particles.saturating_sub(self.particles.len()) {
+            return Err(format!("Too many particles: {} (maximum {})", self.particles.len().saturating_add(count), self.max_particles));
+        }
+
+        let mut rng = rand::thread_rng();
+        let radius = self.particle_radius;
+        let (max_x, max_y) = ((self.piston_position - radius).max(radius), (self.box_size.y - radius).max(radius));
+        let min_distance_squared = 4.0 * radius * radius;
+
+        for _ in 0..count {
+            // A few tries to find a free place, the overlaps left being separated by the first collisions
+            let mut position = Vector2::zero();
+            for _ in 0..20 {
+                position = Vector2::new(rng.gen_range(radius..=max_x), rng.gen_range(radius..=max_y));
+                if self.particles.positions.iter().all(|other| other.distance_to_squared(position) >= min_distance_squared) {
+                    break;
+                }
+            }
+
+            let (vx, vy) = sample_velocity_2d(&mut rng, self.particle_mass, temperature, self.boltzmann_constant);
+            let velocity = Vector2::new(vx, vy);
+            let color = self.color_gradient.at((velocity.magnitude() / self.color_speed) as f64).to_hex_string();
+            self.particles.push(position, velocity, color);
+        }
+        self.piston_target = self.piston_target.max(self.min_piston_position());
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.reset_measurement();
+        self.pressure = 0.0;
+        self.piston_pressure = 0.0;
+    }
+
+    pub fn reset_measurement(&mut self) {
+        self.wall_impulse = 0.0;
+        self.piston_impulse = 0.0;
+        self.measured_time = 0.0;
+    }
+
+    /// Brings the gas to `temperature` by scaling the speeds, or draws new velocities if the gas is at rest.
+    pub fn set_temperature(&mut self, temperature: f32) {
+        let temperature = temperature.max(0.0);
+        let current_temperature = self.calculate_temperature();
+
+        if current_temperature > 0.0 {
+            let scale = (temperature / current_temperature).sqrt();
+            self.particles.velocities.iter_mut().for_each(|velocity| *velocity *= scale);
+        } else {
+            let mut rng = rand::thread_rng();
+            for velocity in &mut self.particles.velocities {
+                let (vx, vy) = sample_velocity_2d(&mut rng, self.particle_mass, temperature, self.boltzmann_constant);
+                *velocity = Vector2::new(vx, vy);
+            }
+        }
+        self.reset_measurement();
+    }
+
+    /// Smallest abscissa of the piston, leaving the particles at most half of the area.
+    pub fn min_piston_position(&self) -> f32 {
+        let particle_area = self.particles.len() as f32 * std::f32::consts::PI * self.particle_radius * self.particle_radius;
+        (0.1 * self.box_size.x).max(2.0 * particle_area / self.box_size.y.max(1.0)).max(4.0 * self.particle_radius).min(self.box_size.x)
+    }
+
+    /// Sets the abscissa the piston moves to, kept between its smallest position and the right of the box.
+    pub fn move_piston(&mut self, position: f32) {
+        self.piston_target = position.clamp(self.min_piston_position(), self.box_size.x);
+    }
+
+    /// Area between the left wall and the piston.
+    pub fn area(&self) -> f32 {
+        self.piston_position * self.box_size.y
+    }
+
+    pub fn calculate_kinetic_energy(&self) -> f32 {
+        self.particles.velocities.iter().map(|velocity| 0.5 * self.particle_mass * velocity.magnitude_squared()).sum()
+    }
+
+    pub fn calculate_temperature(&self) -> f32 {
+        temperature_2d(self.calculate_kinetic_energy(), self.particles.len(), self.boltzmann_constant)
+    }
+
+    /// `P A / (N k T)`, 1 for an ideal gas, with the pressure of the last measurement window.
+    pub fn calculate_compressibility_factor(&self) -> f32 {
+        let thermal_energy = self.particles.len() as f32 * self.boltzmann_constant * self.calculate_temperature();
+        if thermal_energy <= 0.0 {
+            return 0.0;
+        }
+
+        self.pressure * self.area() / thermal_energy
+    }
+
+    /// Histogram of the speeds up to 4 times the most probable speed, with the Maxwell–Boltzmann distribution at the current temperature.
+    pub fn speed_histogram(&self) -> SpeedHistogram {
+        let temperature = self.calculate_temperature();
+        let max_speed = 4.0 * most_probable_speed_2d(self.particle_mass, temperature, self.boltzmann_constant);
+        let speeds: Vec<f32> = self.particles.velocities.iter().map(|velocity| velocity.magnitude()).collect();
+
+        SpeedHistogram::new(&speeds, self.histogram_bins, max_speed, self.particle_mass, temperature, self.boltzmann_constant)
+    }
+
+    /// Advances the gas by `dt`, in substeps short enough for no particle to cross more than its radius,
+    /// and updates the pressure at the end of each measurement window.
+    pub fn update(&mut self, dt: f32) {
+        // The piston heads to its target without overshooting it
+        self.piston_velocity = ((self.piston_target - self.piston_position) / dt).clamp(-self.piston_max_speed, self.piston_max_speed);
+
+        let max_speed = self.particles.velocities.iter().map(|velocity| velocity.magnitude()).fold(self.piston_velocity.abs(), f32::max);
+        self.substeps = ((max_speed * dt / self.particle_radius).ceil() as usize).clamp(1, self.max_substeps);
+        let substep_dt = dt / self.substeps as f32;
+
+        for _ in 0..self.substeps {
+            self.piston_position += self.piston_velocity * substep_dt;
+
+            for i in 0..self.particles.len() {
+                let acceleration = total_acceleration(&self.force_fields, self.particles.positions[i], self.particles.velocities[i]);
+                self.particles.velocities[i] += acceleration * substep_dt;
+                let velocity = self.particles.velocities[i];
+                self.particles.positions[i] += velocity * substep_dt;
+            }
+
+            self.resolve_particle_collisions();
+            self.resolve_wall_collisions();
+        }
+
+        self.measured_time += dt;
+        if self.measured_time >= self.measurement_window {
+            self.pressure = self.wall_impulse / (self.measured_time * 2.0 * (self.piston_position + self.box_size.y));
+            self.piston_pressure = self.piston_impulse / (self.measured_time * self.box_size.y);
+            self.reset_measurement();
+        }
+
+        for i in 0..self.particles.len() {
+            let normalized_speed = self.particles.velocities[i].magnitude() / self.color_speed;
+            self.particles.colors[i] = self.color_gradient.at(normalized_speed as f64).to_hex_string();
+        }
+    }
+
+    /// Separates the overlapping particles and exchanges the normal components of the velocities of the
+    /// approaching ones, the elastic collision of equal masses.
+    ///
+    /// The particles are sorted in a uniform grid of cells as large as a particle, so that a particle can
+    /// only touch the particles of its cell and of the 8 cells around it.
+    fn resolve_particle_collisions(&mut self) {
+        let min_distance = 2.0 * self.particle_radius;
+        let columns = (self.box_size.x / min_distance).ceil().max(1.0) as usize;
+        let rows = (self.box_size.y / min_distance).ceil().max(1.0) as usize;
+        let cell_of = |position: Vector2| (
+            ((position.x / min_distance).max(0.0) as usize).min(columns - 1),
+            ((position.y / min_distance).max(0.0) as usize).min(rows - 1)
+        );
+
+        let mut grid: Vec<Vec<usize>> = vec![Vec::new(); columns * rows];
+        for (i, position) in self.particles.positions.iter().enumerate() {
+            let (x, y) = cell_of(*position);
+            grid[y * columns + x].push(i);
+        }
+
+        for i in 0..self.particles.len() {
+            let (x, y) = cell_of(self.particles.positions[i]);
+
+            for ny in y.saturating_sub(1)..=(y + 1).min(rows - 1) {
+                for nx in x.saturating_sub(1)..=(x + 1).min(columns - 1) {
+                    for &j in grid[ny * columns + nx].iter().filter(|j| **j > i) {
+                        let offset = self.particles.positions[j] - self.particles.positions[i];
+                        let distance = offset.magnitude();
+                        if distance >= min_distance {
+                            continue;
+                        }
+
+                        let normal = if distance > f32::EPSILON { offset / distance } else { Vector2::right() };
+                        let correction = normal * (0.5 * (min_distance - distance));
+                        self.particles.positions[i] -= correction;
+                        self.particles.positions[j] += correction;
+
+                        let approach_speed = (self.particles.velocities[j] - self.particles.velocities[i]) * normal;
+                        if approach_speed < 0.0 {
+                            let exchange = normal * approach_speed;
+                            self.particles.velocities[i] += exchange;
+                            self.particles.velocities[j] -= exchange;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Keeps the particles between the walls and the piston, reflecting the ones moving outwards and
+    /// summing the momentum they give to the walls.
+    ///
+    /// A particle bounces on the piston with its velocity relative to the piston reversed, so that the
+    /// piston gives energy to the gas when pushing it and takes some when pulled back.
+    fn resolve_wall_collisions(&mut self) {
+        let (radius, mass) = (self.particle_radius, self.particle_mass);
+        let (left, right, top, bottom) = (radius, (self.piston_position - radius).max(radius), radius, (self.box_size.y - radius).max(radius));
+
+        for i in 0..self.particles.len() {
+            let (position, velocity) = (&mut self.particles.positions[i], &mut self.particles.velocities[i]);
+
+            if position.x < left {
+                position.x = left;
+                if velocity.x < 0.0 {
+                    self.wall_impulse += -2.0 * mass * velocity.x;
+                    velocity.x = -velocity.x;
+                }
+            }
+            if position.x > right {
+                position.x = right;
+                if velocity.x > self.piston_velocity {
+                    let impulse = 2.0 * mass * (velocity.x - self.piston_velocity);
+                    self.wall_impulse += impulse;
+                    self.piston_impulse += impulse;
+                    velocity.x = 2.0 * self.piston_velocity - velocity.x;
+                }
+            }
+            if position.y < top {
+                position.y = top;
+                if velocity.y < 0.0 {
+                    self.wall_impulse += -2.0 * mass * velocity.y;
+                    velocity.y = -velocity.y;
+                }
+            }
+            if position.y > bottom {
+                position.y = bottom;
+                if velocity.y > 0.0 {
+                    self.wall_impulse += 2.0 * mass * velocity.y;
+                    velocity.y = -velocity.y;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod data;
+pub mod system;
+pub mod main;
\ No newline at end of file
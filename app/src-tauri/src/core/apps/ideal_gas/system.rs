@@ -0,0 +1,126 @@
+use crate::core::app_system::simulation::template::SimulationTemplate;
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::sciences::maths::vector2::Vector2;
+
+use crate::core::sciences::physics::force_fields::handle_force_field_event;
+
+use super::main::IdealGas;
+use super::data::*;
+
+/// Obligatory implementation of the `SimulationTemplate` trait for the `IdealGas` struct.
+impl SimulationTemplate for IdealGas {
+    fn initialize(&mut self, renderer_size: Vector2, serialized_data: Option<String>) -> Result<(), String> {
+        self.clear();
+        self.box_size = renderer_size;
+        self.piston_position = renderer_size.x;
+        self.piston_target = renderer_size.x;
+        self.piston_velocity = 0.0;
+
+        let starter_data: StarterData = match serialized_data {
+            Some(data) => match serde_json::from_str(&data) {
+                Ok(deserialized_data) => deserialized_data,
+                Err(e) => return Err(e.to_string())
+            },
+            None => return Ok(())
+        };
+
+        self.particle_radius = starter_data.particle_radius.max(0.5);
+        self.particle_mass = starter_data.particle_mass.max(f32::EPSILON);
+        self.add_particles(starter_data.particle_count, starter_data.temperature)?;
+
+        Ok(())
+    }
+
+    fn next_step(&mut self, dt: f32) -> Result<(), String> {
+        self.update(dt);
+
+        Ok(())
+    }
+
+    fn get_data_to_render(&self) -> Result<Box<dyn DataToSend>, String> {
+        let data_to_render = RendererData {
+            particles: self.particles.clone(),
+            particle_radius: self.particle_radius,
+            piston_position: self.piston_position,
+            area: self.area(),
+            kinetic_energy: self.calculate_kinetic_energy(),
+            temperature: self.calculate_temperature(),
+            pressure: self.pressure,
+            piston_pressure: self.piston_pressure,
+            compressibility_factor: self.calculate_compressibility_factor(),
+            histogram: self.speed_histogram(),
+            substeps: self.substeps,
+            force_fields: self.force_fields.clone()
+        };
+
+        Ok(Box::new(data_to_render))
+    }
+
+    fn event_handler(&mut self, event: String, data: Option<String>) -> Result<(), String> {
+        match event.as_str() {
+            "set_settings" => {
+                match data {
+                    Some(data) => {
+                        let settings: EventSettings = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.piston_max_speed = settings.piston_max_speed.max(0.0);
+                        self.measurement_window = settings.measurement_window.max(0.05);
+                        self.histogram_bins = settings.histogram_bins.clamp(1, 200);
+                    },
+                    None => return Err("No data provided for the 'set_settings' event".to_string())
+                }
+            },
+            "add_particles" => {
+                match data {
+                    Some(data) => {
+                        let particles: EventAddParticles = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.add_particles(particles.count, particles.temperature)?;
+                    },
+                    None => return Err("No data provided for the 'add_particles' event".to_string())
+                }
+            },
+            "set_temperature" => {
+                match data {
+                    Some(data) => {
+                        let temperature: EventTemperature = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.set_temperature(temperature.temperature);
+                    },
+                    None => return Err("No data provided for the 'set_temperature' event".to_string())
+                }
+            },
+            "move_piston" => {
+                match data {
+                    Some(data) => {
+                        let piston: EventPiston = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.move_piston(piston.position);
+                    },
+                    None => return Err("No data provided for the 'move_piston' event".to_string())
+                }
+            },
+            "clear" => {
+                self.clear();
+            },
+            "add_force_field" | "remove_force_field" | "clear_force_fields" => {
+                handle_force_field_event(&mut self.force_fields, &event, data)?;
+            },
+            _ => return Err("Unknown event".to_string())
+        }
+
+        Ok(())
+    }
+}
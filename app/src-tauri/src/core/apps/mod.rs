@@ -1,3 +1,10 @@
 pub mod bouncing_balls;
+pub mod electrostatics;
+pub mod flip;
 pub mod fluid;
-pub mod rigibody;
\ No newline at end of file
+pub mod fluid_rigid;
+pub mod ideal_gas;
+pub mod nbody;
+pub mod rigibody;
+pub mod sand;
+pub mod smoke;
\ No newline at end of file
@@ -0,0 +1,66 @@
+use serde::{Serialize, Deserialize};
+
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::physics::force_fields::ForceField;
+use crate::core::sciences::physics::gravitation::TreeCell;
+
+use super::generators::Generator;
+
+/// The bodies of the simulation, one entry per body in each array.
+#[derive(Serialize, Clone, Default)]
+pub struct Bodies {
+    pub positions: Vec<Vector2>,
+    pub velocities: Vec<Vector2>,
+    pub masses: Vec<f32>,
+    pub radii: Vec<f32>,
+    pub colors: Vec<String>, // store in hex format and not in colorgrad::Color to allow serialization
+}
+
+impl Bodies {
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn push(&mut self, position: Vector2, velocity: Vector2, mass: f32, radius: f32, color: String) {
+        self.positions.push(position);
+        self.velocities.push(velocity);
+        self.masses.push(mass);
+        self.radii.push(radius);
+        self.colors.push(color);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Bodies::default();
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct RendererData {
+    pub bodies: Bodies,
+    /// Cells of the Barnes–Hut tree, empty unless the tree is shown.
+    pub tree_cells: Vec<TreeCell>,
+    pub kinetic_energy: f32,
+    pub potential_energy: f32,
+    pub substeps: usize,
+    pub force_fields: Vec<ForceField>,
+}
+
+impl DataToSend for RendererData {}
+
+#[derive(Deserialize, Clone)]
+pub struct StarterData {
+    pub generators: Vec<Generator>,
+}
+
+#[derive(Deserialize)]
+pub struct EventSettings {
+    pub gravitational_constant: f32,
+    pub softening: f32,
+    pub theta: f32,
+    pub show_tree: bool,
+}
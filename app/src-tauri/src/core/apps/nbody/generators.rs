@@ -0,0 +1,168 @@
+use std::cmp::Ordering;
+use std::f32::consts::PI;
+
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use crate::core::sciences::maths::Vector2;
+
+/// Initial conditions of a group of bodies, generated by the backend from a few parameters.
+///
+/// Every group is placed at `center` and moves as a whole at `velocity`, so that several groups can collide.
+/// Serialized with an internal `type` tag, e.g.
+/// `{ "type": "plummer_sphere", "center": { "x": 400, "y": 300 }, "velocity": { "x": 0, "y": 0 }, "body_count": 500, "total_mass": 100000, "scale_radius": 60 }`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Generator {
+    /// A single body.
+    Body { position: Vector2, velocity: Vector2, mass: f32 },
+    /// Light bodies on circular orbits around a central body, between `inner_radius` and `outer_radius`.
+    Orbit { center: Vector2, velocity: Vector2, central_mass: f32, body_count: usize, body_mass: f32, inner_radius: f32, outer_radius: f32 },
+    /// Exponential disc of stars of total mass `disc_mass` and radius `radius`, rotating around a core,
+    /// each star on the circular orbit of the mass inside it. The disc starts at a tenth of its radius,
+    /// the core standing for the bulge.
+    DiscGalaxy { center: Vector2, velocity: Vector2, core_mass: f32, body_count: usize, disc_mass: f32, radius: f32 },
+    /// Plummer sphere (Plummer 1911) of scale radius `scale_radius`, seen from above, with isotropic
+    /// random velocities scaled to the virial equilibrium.
+    PlummerSphere { center: Vector2, velocity: Vector2, body_count: usize, total_mass: f32, scale_radius: f32 },
+}
+
+impl Generator {
+    /// Number of bodies generated.
+    pub fn body_count(&self) -> usize {
+        match self {
+            Generator::Body { .. } => 1,
+            Generator::Orbit { body_count, .. } => body_count.saturating_add(1),
+            Generator::DiscGalaxy { body_count, .. } => body_count.saturating_add(1),
+            Generator::PlummerSphere { body_count, .. } => *body_count,
+        }
+    }
+
+    /// Generates the positions, velocities and masses of the bodies, in equilibrium for the
+    /// gravitational constant `gravitational_constant` and the softening length `softening`.
+    pub fn generate(&self, gravitational_constant: f32, softening: f32) -> Result<Vec<(Vector2, Vector2, f32)>, String> {
+        match self {
+            Generator::Body { position, velocity, mass } => Ok(vec![(*position, *velocity, mass.max(f32::EPSILON))]),
+            Generator::Orbit { center, velocity, central_mass, body_count, body_mass, inner_radius, outer_radius } => {
+                check_radius("inner radius", *inner_radius, true)?;
+                check_radius("outer radius", *outer_radius, true)?;
+
+                let mut rng = rand::thread_rng();
+                let (inner_radius, outer_radius) = (inner_radius.min(*outer_radius), inner_radius.max(*outer_radius));
+
+                let mut bodies = vec![(Vector2::zero(), Vector2::zero(), central_mass.max(f32::EPSILON))];
+                for _ in 0..*body_count {
+                    let radius = rng.gen_range(inner_radius..=outer_radius);
+                    let angle = rng.gen_range(0.0..2.0 * PI);
+                    let direction = Vector2::new(angle.cos(), angle.sin());
+                    let speed = circular_speed(gravitational_constant * central_mass, radius, softening);
+                    bodies.push((direction * radius, direction.perpendicular() * speed, body_mass.max(f32::EPSILON)));
+                }
+
+                Ok(place_group(bodies, *center, *velocity))
+            },
+            Generator::DiscGalaxy { center, velocity, core_mass, body_count, disc_mass, radius } => {
+                check_radius("radius", *radius, false)?;
+
+                let mut rng = rand::thread_rng();
+                let scale_length = radius / 3.0;
+                let body_mass = (disc_mass / (*body_count).max(1) as f32).max(f32::EPSILON);
+
+                // The radius of an exponential disc follows a gamma law of shape 2, cut at the bulge and at `radius`
+                let mut radii: Vec<f32> = (0..*body_count).map(|_| loop {
+                    let r = -scale_length * (rng.gen_range(f32::EPSILON..1.0) * rng.gen_range(f32::EPSILON..1.0)).ln();
+                    if r >= radius / 10.0 && r <= *radius {
+                        break r;
+                    }
+                }).collect();
+                radii.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+                let mut bodies = vec![(Vector2::zero(), Vector2::zero(), core_mass.max(f32::EPSILON))];
+                for (k, r) in radii.into_iter().enumerate() {
+                    let angle = rng.gen_range(0.0..2.0 * PI);
+                    let direction = Vector2::new(angle.cos(), angle.sin());
+                    let enclosed_mass = core_mass + k as f32 * body_mass;
+                    let speed = circular_speed(gravitational_constant * enclosed_mass, r, softening);
+                    bodies.push((direction * r, direction.perpendicular() * speed, body_mass));
+                }
+
+                Ok(place_group(bodies, *center, *velocity))
+            },
+            Generator::PlummerSphere { center, velocity, body_count, total_mass, scale_radius } => {
+                check_radius("scale radius", *scale_radius, false)?;
+
+                let mut rng = rand::thread_rng();
+                let body_mass = (total_mass / (*body_count).max(1) as f32).max(f32::EPSILON);
+
+                // The projected mass inside R is R² / (R² + a²), cut at 99% to avoid the far outliers
+                let mut bodies: Vec<(Vector2, Vector2, f32)> = (0..*body_count).map(|_| {
+                    let fraction: f32 = rng.gen_range(0.0..0.99);
+                    let radius = scale_radius * (fraction / (1.0 - fraction)).sqrt();
+                    let angle = rng.gen_range(0.0..2.0 * PI);
+                    let velocity = Vector2::new(gaussian(&mut rng), gaussian(&mut rng));
+                    (Vector2::new(angle.cos(), angle.sin()) * radius, velocity, body_mass)
+                }).collect();
+
+                // Virial equilibrium, 2K + W = 0, the potential energy W being computed once directly
+                let mut potential_energy = 0.0;
+                for i in 0..bodies.len() {
+                    for j in i + 1..bodies.len() {
+                        let distance = (bodies[i].0.distance_to_squared(bodies[j].0) + softening * softening).sqrt();
+                        if distance > 0.0 {
+                            potential_energy -= gravitational_constant * bodies[i].2 * bodies[j].2 / distance;
+                        }
+                    }
+                }
+                let kinetic_energy: f32 = bodies.iter().map(|(_, velocity, mass)| 0.5 * mass * velocity.magnitude_squared()).sum();
+                if kinetic_energy > 0.0 {
+                    let scale = (-0.5 * potential_energy / kinetic_energy).sqrt();
+                    for body in &mut bodies {
+                        body.1 *= scale;
+                    }
+                }
+
+                Ok(place_group(bodies, *center, *velocity))
+            },
+        }
+    }
+}
+
+/// Checks that a radius of a generator is finite and positive, or only non-negative when `allow_zero` is set.
+fn check_radius(name: &str, radius: f32, allow_zero: bool) -> Result<(), String> {
+    if !radius.is_finite() || radius < 0.0 || (radius == 0.0 && !allow_zero) {
+        return Err(format!("The {} of a generator must be {}, got {}", name, if allow_zero { "non-negative" } else { "positive" }, radius));
+    }
+
+    Ok(())
+}
+
+/// Speed of a circular orbit of radius `radius` around a softened point mass, `gravitational_parameter` being G·M.
+fn circular_speed(gravitational_parameter: f32, radius: f32, softening: f32) -> f32 {
+    let distance_squared = radius * radius + softening * softening;
+    (gravitational_parameter.max(0.0) * radius * radius / (distance_squared * distance_squared.sqrt())).sqrt()
+}
+
+/// Sample of the standard normal law, with the Box–Muller transform.
+fn gaussian(rng: &mut impl Rng) -> f32 {
+    let u: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let v: f32 = rng.gen_range(0.0..1.0);
+    (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+/// Moves the bodies, generated around the origin, to the frame of the group: their center of mass
+/// at `center` and their total momentum the one of the group moving at `velocity`.
+fn place_group(mut bodies: Vec<(Vector2, Vector2, f32)>, center: Vector2, velocity: Vector2) -> Vec<(Vector2, Vector2, f32)> {
+    let total_mass: f32 = bodies.iter().map(|(_, _, mass)| mass).sum();
+    if total_mass <= 0.0 {
+        return bodies;
+    }
+
+    let center_of_mass = bodies.iter().fold(Vector2::zero(), |sum, (position, _, mass)| sum + *position * *mass) / total_mass;
+    let mean_velocity = bodies.iter().fold(Vector2::zero(), |sum, (_, velocity, mass)| sum + *velocity * *mass) / total_mass;
+    for body in &mut bodies {
+        body.0 = body.0 - center_of_mass + center;
+        body.1 = body.1 - mean_velocity + velocity;
+    }
+
+    bodies
+}
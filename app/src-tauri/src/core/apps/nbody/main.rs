@@ -0,0 +1,185 @@
+use colorgrad::Gradient;
+use rayon::prelude::*;
+
+use crate::core::sciences::maths::Vector2;
+use crate::core::sciences::physics::force_fields::{ForceField, total_acceleration};
+use crate::core::sciences::physics::gravitation::QuadTree;
+
+use super::data::Bodies;
+use super::generators::Generator;
+
+/// Self-gravitating bodies: planets, star clusters, galaxies.
+///
+/// The gravitation of every body on the others is approximated with a Barnes–Hut quadtree and softened,
+/// and the bodies are moved with the kick-drift-kick leapfrog, a symplectic scheme whose energy oscillates
+/// without drifting. The step is split in substeps when the accelerations get too large for the softening length.
+pub struct NBody {
+    // BODY PROPERTIES
+    pub bodies: Bodies,
+    /// Accelerations of the bodies at their current positions, kept from a step to the next by the leapfrog.
+    pub accelerations: Vec<Vector2>,
+    /// Whether the accelerations must be computed again before the next step, after a change of the bodies or the settings.
+    pub accelerations_outdated: bool,
+    pub max_bodies: usize,
+    // GRAVITATION PROPERTIES
+    pub gravitational_constant: f32,
+    pub softening: f32,
+    /// Opening angle of the Barnes–Hut approximation, 0 for the exact sum.
+    pub theta: f32,
+    pub tree: QuadTree,
+    /// External force fields, added to the gravitation.
+    pub force_fields: Vec<ForceField>,
+    // TIMESTEP PROPERTIES
+    /// Fraction of the time needed to cross the softening length from rest at the largest acceleration.
+    pub timestep_accuracy: f32,
+    pub max_substeps: usize,
+    pub substeps: usize,
+    // RENDER PROPERTIES
+    /// Radius of a body of unit mass, the radii scaling with the cube root of the mass.
+    pub radius_scale: f32,
+    pub min_radius: f32,
+    pub show_tree: bool,
+    /// Speed mapped to the end of the color gradient.
+    pub color_speed: f32,
+    pub color_gradient: Gradient
+}
+
+impl NBody {
+    pub fn new(color_gradient: Gradient) -> Self {
+        NBody {
+            // BODY PROPERTIES
+            bodies: Bodies::default(),
+            accelerations: Vec::new(),
+            accelerations_outdated: true,
+            max_bodies: 5000,
+            // GRAVITATION PROPERTIES
+            gravitational_constant: 1.0,
+            softening: 5.0,
+            theta: 0.5,
+            tree: QuadTree::new(&[], &[]),
+            force_fields: Vec::new(),
+            // TIMESTEP PROPERTIES
+            timestep_accuracy: 0.2,
+            max_substeps: 20,
+            substeps: 1,
+            // RENDER PROPERTIES
+            radius_scale: 0.2,
+            min_radius: 1.5,
+            show_tree: false,
+            color_speed: 300.0,
+            color_gradient
+        }
+    }
+
+    /// Radius drawn for a body of mass `mass`, as for bodies of equal density.
+    pub fn body_radius(&self, mass: f32) -> f32 {
+        (self.radius_scale * mass.cbrt()).max(self.min_radius)
+    }
+
+    pub fn add_body(&mut self, position: Vector2, velocity: Vector2, mass: f32) {
+        let radius = self.body_radius(mass);
+        let color = self.color_gradient.at((velocity.magnitude() / self.color_speed) as f64).to_hex_string();
+        self.bodies.push(position, velocity, mass, radius, color);
+        self.accelerations_outdated = true;
+    }
+
+    /// Adds the bodies of `generator`, checking that they do not exceed the maximum body count.
+    pub fn generate(&mut self, generator: &Generator) -> Result<(), String> {
+        let body_count = self.bodies.len().saturating_add(generator.body_count());
+        if body_count > self.max_bodies {
+            return Err(format!("Too many bodies: {} (maximum {})", body_count, self.max_bodies));
+        }
+
+        for (position, velocity, mass) in generator.generate(self.gravitational_constant, self.softening)? {
+            self.add_body(position, velocity, mass);
+        }
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.bodies.clear();
+        self.accelerations.clear();
+        self.tree = QuadTree::new(&[], &[]);
+        self.accelerations_outdated = true;
+    }
+
+    /// Rebuilds the tree at the current positions and computes the accelerations of all the bodies.
+    pub fn compute_accelerations(&mut self) {
+        self.tree = QuadTree::new(&self.bodies.positions, &self.bodies.masses);
+
+        // The bodies only read the tree, so they are independent
+        self.accelerations = (0..self.bodies.len()).into_par_iter().map(|i| {
+            let gravitation = self.tree.acceleration(&self.bodies.positions, &self.bodies.masses, i, self.theta, self.softening);
+            gravitation * self.gravitational_constant
+                + total_acceleration(&self.force_fields, self.bodies.positions[i], self.bodies.velocities[i])
+        }).collect();
+
+        self.accelerations_outdated = false;
+    }
+
+    /// Splits `dt` so that no body crosses more than a fraction of the softening length from rest during a substep.
+    pub fn plan_substeps(&mut self, dt: f32) -> f32 {
+        let max_acceleration = self.accelerations.iter().map(|acceleration| acceleration.magnitude()).fold(0.0, f32::max);
+        let stable_dt = if max_acceleration > 0.0 {
+            self.timestep_accuracy * (self.softening.max(f32::EPSILON) / max_acceleration).sqrt()
+        } else {
+            f32::INFINITY
+        };
+
+        self.substeps = ((dt / stable_dt).ceil() as usize).clamp(1, self.max_substeps);
+        dt / self.substeps as f32
+    }
+
+    /// Advances the bodies by `dt` with the kick-drift-kick leapfrog.
+    pub fn update(&mut self, dt: f32) {
+        if self.bodies.is_empty() {
+            return;
+        }
+
+        if self.accelerations_outdated {
+            self.compute_accelerations();
+        }
+
+        let substep_dt = self.plan_substeps(dt);
+        for _ in 0..self.substeps {
+            for i in 0..self.bodies.len() {
+                self.bodies.velocities[i] += self.accelerations[i] * (0.5 * substep_dt);
+                self.bodies.positions[i] += self.bodies.velocities[i] * substep_dt;
+            }
+
+            self.compute_accelerations();
+
+            for i in 0..self.bodies.len() {
+                self.bodies.velocities[i] += self.accelerations[i] * (0.5 * substep_dt);
+            }
+        }
+
+        for i in 0..self.bodies.len() {
+            let normalized_speed = self.bodies.velocities[i].magnitude() / self.color_speed;
+            self.bodies.colors[i] = self.color_gradient.at(normalized_speed as f64).to_hex_string();
+        }
+    }
+
+    pub fn calculate_kinetic_energy(&self) -> f32 {
+        (0..self.bodies.len()).map(|i| 0.5 * self.bodies.masses[i] * self.bodies.velocities[i].magnitude_squared()).sum()
+    }
+
+    /// Softened gravitational potential energy of the bodies, with the tree of the last step if it is up to date.
+    ///
+    /// The force fields are not included, most of them not deriving from a potential.
+    pub fn calculate_potential_energy(&self) -> f32 {
+        let rebuilt_tree;
+        let tree = if self.accelerations_outdated {
+            rebuilt_tree = QuadTree::new(&self.bodies.positions, &self.bodies.masses);
+            &rebuilt_tree
+        } else {
+            &self.tree
+        };
+
+        // Each pair is counted twice
+        0.5 * self.gravitational_constant * (0..self.bodies.len()).map(|i| {
+            self.bodies.masses[i] * tree.potential(&self.bodies.positions, &self.bodies.masses, i, self.theta, self.softening)
+        }).sum::<f32>()
+    }
+}
@@ -0,0 +1,4 @@
+pub mod data;
+pub mod system;
+pub mod main;
+pub mod generators;
\ No newline at end of file
@@ -0,0 +1,94 @@
+use crate::core::app_system::simulation::template::SimulationTemplate;
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::sciences::maths::vector2::Vector2;
+
+use crate::core::sciences::physics::force_fields::handle_force_field_event;
+
+use super::main::NBody;
+use super::data::*;
+use super::generators::Generator;
+
+/// Obligatory implementation of the `SimulationTemplate` trait for the `NBody` struct.
+impl SimulationTemplate for NBody {
+    fn initialize(&mut self, _renderer_size: Vector2, serialized_data: Option<String>) -> Result<(), String> {
+        self.clear();
+
+        let starter_data: StarterData = match serialized_data {
+            Some(data) => match serde_json::from_str(&data) {
+                Ok(deserialized_data) => deserialized_data,
+                Err(e) => return Err(e.to_string())
+            },
+            None => return Ok(())
+        };
+
+        for generator in &starter_data.generators {
+            self.generate(generator)?;
+        }
+
+        Ok(())
+    }
+
+    fn next_step(&mut self, dt: f32) -> Result<(), String> {
+        self.update(dt);
+
+        Ok(())
+    }
+
+    fn get_data_to_render(&self) -> Result<Box<dyn DataToSend>, String> {
+        let data_to_render = RendererData {
+            bodies: self.bodies.clone(),
+            tree_cells: if self.show_tree { self.tree.cells() } else { Vec::new() },
+            kinetic_energy: self.calculate_kinetic_energy(),
+            potential_energy: self.calculate_potential_energy(),
+            substeps: self.substeps,
+            force_fields: self.force_fields.clone()
+        };
+
+        Ok(Box::new(data_to_render))
+    }
+
+    fn event_handler(&mut self, event: String, data: Option<String>) -> Result<(), String> {
+        match event.as_str() {
+            "set_settings" => {
+                match data {
+                    Some(data) => {
+                        let settings: EventSettings = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.gravitational_constant = settings.gravitational_constant.max(0.0);
+                        self.softening = settings.softening.max(0.1);
+                        self.theta = settings.theta.clamp(0.0, 2.0);
+                        self.show_tree = settings.show_tree;
+                        self.accelerations_outdated = true;
+                    },
+                    None => return Err("No data provided for the 'set_settings' event".to_string())
+                }
+            },
+            "generate" => {
+                match data {
+                    Some(data) => {
+                        let generator: Generator = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.generate(&generator)?;
+                    },
+                    None => return Err("No data provided for the 'generate' event".to_string())
+                }
+            },
+            "clear" => {
+                self.clear();
+            },
+            "add_force_field" | "remove_force_field" | "clear_force_fields" => {
+                handle_force_field_event(&mut self.force_fields, &event, data)?;
+                self.accelerations_outdated = true;
+            },
+            _ => return Err("Unknown event".to_string())
+        }
+
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use crate::core::app_system::simulation::template::SimulationTemplate;
 use crate::core::app_system::simulation::renderer::DataToSend;
 use crate::core::sciences::maths::Vector2;
 use crate::core::sciences::physics::rigid_body::r#struct::ShapeType;
+use crate::core::sciences::physics::force_fields::handle_force_field_event;
 
 use super::main::RigidSimulation;
 use super::data::*;
@@ -52,7 +53,25 @@ fn initialize(&mut self, renderer_size: Vector2, serialized_data: Option<String>
     }
 
     fn event_handler(&mut self, event: String, data: Option<String>) -> Result<(), String> {
-        unimplemented!();
+        match event.as_str() {
+            "set_integrator" => {
+                match data {
+                    Some(data) => {
+                        self.work_space.integrator = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+                    },
+                    None => return Err("No data provided for the 'set_integrator' event".to_string())
+                }
+            },
+            "add_force_field" | "remove_force_field" | "clear_force_fields" => {
+                handle_force_field_event(&mut self.work_space.force_fields, &event, data)?;
+            },
+            _ => return Err("Unknown event".to_string())
+        }
+
+        Ok(())
     }
 
     fn get_data_to_render(&self) -> Result<Box<dyn DataToSend>, String> {
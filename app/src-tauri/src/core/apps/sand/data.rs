@@ -0,0 +1,103 @@
+use serde::{Serialize, Deserialize};
+
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::physics::force_fields::ForceField;
+
+/// The grains of the simulation, one entry per grain in each array.
+#[derive(Serialize, Clone, Default)]
+pub struct Grains {
+    pub positions: Vec<Vector2>,
+    pub velocities: Vec<Vector2>,
+    pub angles: Vec<f32>,
+    pub angular_velocities: Vec<f32>,
+    pub radii: Vec<f32>,
+    pub colors: Vec<String>, // store in hex format and not in colorgrad::Color to allow serialization
+}
+
+impl Grains {
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn push(&mut self, position: Vector2, velocity: Vector2, radius: f32, color: String) {
+        self.positions.push(position);
+        self.velocities.push(velocity);
+        self.angles.push(0.0);
+        self.angular_velocities.push(0.0);
+        self.radii.push(radius);
+        self.colors.push(color);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Grains::default();
+    }
+}
+
+/// A static wall segment, the box walls being segments too.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub start: Vector2,
+    pub end: Vector2,
+}
+
+impl Segment {
+    pub fn new(start: Vector2, end: Vector2) -> Self {
+        Segment { start, end }
+    }
+
+    /// Returns the point of the segment closest to `point`.
+    pub fn closest_point(&self, point: Vector2) -> Vector2 {
+        let direction = self.end - self.start;
+        let length_squared = direction.magnitude_squared();
+        if length_squared <= f32::EPSILON {
+            return self.start;
+        }
+
+        let t = ((point - self.start) * direction / length_squared).clamp(0.0, 1.0);
+        self.start + direction * t
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct RendererData {
+    pub grains: Grains,
+    pub segments: Vec<Segment>,
+    pub substeps: usize,
+    /// Slope of the pile in degrees, `None` when there is no pile to measure.
+    pub repose_angle: Option<f32>,
+    pub force_fields: Vec<ForceField>,
+}
+
+impl DataToSend for RendererData {}
+
+#[derive(Deserialize, Clone)]
+pub struct StarterData {
+    #[serde(default)]
+    pub positions: Vec<Vector2>,
+    /// Walls added to the box, e.g. a hopper.
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+}
+
+#[derive(Deserialize)]
+pub struct EventSettings {
+    pub gravity: f32,
+    pub grain_radius: f32,
+    pub polydispersity: f32,
+    pub normal_stiffness: f32,
+    pub restitution: f32,
+    pub friction: f32,
+    pub rolling_friction: f32,
+    pub pour_rate: f32,
+}
+
+/// Position of the pointer pouring grains.
+#[derive(Deserialize)]
+pub struct EventPour {
+    pub position: Vector2,
+}
@@ -0,0 +1,404 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use colorgrad::Gradient;
+use rand::Rng;
+
+use crate::core::sciences::maths::Vector2;
+use crate::core::sciences::physics::force_fields::{ForceField, total_acceleration};
+
+use super::data::{Grains, Segment};
+
+/// Angular velocity below which the rolling resistance fades out, avoiding oscillations around rest.
+const ROLLING_VELOCITY_SCALE: f32 = 0.5;
+
+/// Granular material simulated with the discrete element method (Cundall & Strack 1979).
+///
+/// Grains are rigid discs interacting through soft contacts: a linear spring-dashpot along the
+/// normal, a tangential spring capped by Coulomb friction, and a rolling resistance torque.
+/// The contacts are found with a uniform grid, and the step is split into substeps short
+/// enough to resolve each collision.
+pub struct Sand {
+    // GRAIN PROPERTIES
+    pub grains: Grains,
+    /// Elongation of the tangential spring of each contact, keyed by the grain indices,
+    /// the second one being `usize::MAX - k` for the segment `k`.
+    pub contacts: HashMap<(usize, usize), Vector2>,
+    pub max_grains: usize,
+    // MATERIAL PROPERTIES
+    pub grain_radius: f32,
+    /// Relative spread of the radii, avoiding the crystallization of equal discs.
+    pub polydispersity: f32,
+    pub grain_density: f32,
+    pub normal_stiffness: f32,
+    /// Ratio of the tangential to the normal stiffness.
+    pub tangential_stiffness_ratio: f32,
+    pub restitution: f32,
+    pub friction: f32,
+    pub rolling_friction: f32,
+    pub gravity: f32,
+    /// External force fields, added to the gravity.
+    pub force_fields: Vec<ForceField>,
+    pub max_substeps: usize,
+    pub substeps: usize,
+    // BOUNDARY PROPERTIES
+    pub box_size: Vector2,
+    pub segments: Vec<Segment>,
+    // INTERACTION PROPERTIES
+    pub pouring: bool,
+    pub pour_position: Vector2,
+    pub pour_rate: f32,
+    pub pour_accumulator: f32,
+    // GRID PROPERTIES
+    pub cell_size: f32,
+    pub grid_width: usize,
+    pub grid_height: usize,
+    pub cells: Vec<Vec<usize>>,
+    // OTHER PROPERTIES
+    pub repose_angle: Option<f32>,
+    pub color_gradient: Gradient
+}
+
+impl Sand {
+    pub fn new(color_gradient: Gradient) -> Self {
+        Sand {
+            // GRAIN PROPERTIES
+            grains: Grains::default(),
+            contacts: HashMap::new(),
+            max_grains: 3000,
+            // MATERIAL PROPERTIES
+            grain_radius: 4.0,
+            polydispersity: 0.2,
+            grain_density: 1.0,
+            normal_stiffness: 4e6,
+            tangential_stiffness_ratio: 2.0 / 7.0,
+            restitution: 0.3,
+            friction: 0.5,
+            rolling_friction: 0.1,
+            gravity: 500.0,
+            force_fields: Vec::new(),
+            max_substeps: 80,
+            substeps: 1,
+            // BOUNDARY PROPERTIES
+            box_size: Vector2::new(800.0, 600.0),
+            segments: Vec::new(),
+            // INTERACTION PROPERTIES
+            pouring: false,
+            pour_position: Vector2::zero(),
+            pour_rate: 200.0,
+            pour_accumulator: 0.0,
+            // GRID PROPERTIES
+            cell_size: 8.0,
+            grid_width: 0,
+            grid_height: 0,
+            cells: Vec::new(),
+            // OTHER PROPERTIES
+            repose_angle: None,
+            color_gradient
+        }
+    }
+
+    /// Replaces the walls by the box of size `box_size` plus the extra `segments`.
+    pub fn set_walls(&mut self, box_size: Vector2, segments: Vec<Segment>) {
+        self.box_size = box_size;
+        let corners = [Vector2::zero(), Vector2::new(box_size.x, 0.0), box_size, Vector2::new(0.0, box_size.y)];
+        self.segments = (0..4).map(|k| Segment::new(corners[k], corners[(k + 1) % 4])).collect();
+        self.segments.extend(segments);
+        self.contacts.clear();
+    }
+
+    /// Adds a grain with a random radius and colour.
+    pub fn add_grain(&mut self, position: Vector2, velocity: Vector2) {
+        let mut rng = rand::thread_rng();
+        let radius = self.grain_radius * (1.0 - self.polydispersity.clamp(0.0, 0.9) * rng.gen::<f32>());
+        let color = self.color_gradient.at(rng.gen::<f64>()).to_hex_string();
+        self.grains.push(position, velocity, radius, color);
+    }
+
+    pub fn clear(&mut self) {
+        self.grains.clear();
+        self.contacts.clear();
+        self.repose_angle = None;
+    }
+
+    fn mass(&self, i: usize) -> f32 {
+        self.grain_density * PI * self.grains.radii[i] * self.grains.radii[i]
+    }
+
+    /// Moment of inertia of a disc.
+    fn inertia(&self, i: usize) -> f32 {
+        0.5 * self.mass(i) * self.grains.radii[i] * self.grains.radii[i]
+    }
+
+    /// Normal damping coefficient giving the coefficient of restitution for a pair of effective mass `effective_mass`.
+    fn normal_damping(&self, effective_mass: f32) -> f32 {
+        let log_restitution = self.restitution.clamp(1e-3, 1.0).ln();
+        let damping_ratio = -log_restitution / (PI * PI + log_restitution * log_restitution).sqrt();
+        2.0 * damping_ratio * (effective_mass * self.normal_stiffness).sqrt()
+    }
+
+    /// Splits `dt` in substeps of at most a twentieth of the collision duration of the lightest grains.
+    fn plan_substeps(&mut self, dt: f32) -> f32 {
+        let min_radius = self.grains.radii.iter().cloned().fold(self.grain_radius, f32::min);
+        let effective_mass = 0.5 * self.grain_density * PI * min_radius * min_radius;
+        let collision_duration = PI * (effective_mass / self.normal_stiffness.max(f32::EPSILON)).sqrt();
+
+        self.substeps = ((dt / (collision_duration / 20.0)).ceil() as usize).clamp(1, self.max_substeps);
+        dt / self.substeps as f32
+    }
+
+    /// Spawns the grains poured under the pointer, where there is room for them.
+    fn pour(&mut self, dt: f32) {
+        if !self.pouring {
+            self.pour_accumulator = 0.0;
+            return;
+        }
+
+        self.pour_accumulator += self.pour_rate * dt;
+        let mut rng = rand::thread_rng();
+
+        while self.pour_accumulator >= 1.0 && self.grains.len() < self.max_grains {
+            self.pour_accumulator -= 1.0;
+
+            let position = self.pour_position + Vector2::new(rng.gen_range(-3.0..=3.0) * self.grain_radius, 0.0);
+            let is_free = self.grains.positions.iter().zip(self.grains.radii.iter())
+                .all(|(other, radius)| other.distance_to(position) > radius + self.grain_radius);
+
+            if is_free {
+                self.add_grain(position, Vector2::down() * 50.0);
+            }
+        }
+    }
+
+    /// Sorts the grains in a uniform grid whose cells are as large as the largest grain diameter.
+    fn update_grid(&mut self) {
+        self.cell_size = 2.0 * self.grains.radii.iter().cloned().fold(self.grain_radius, f32::max);
+        self.grid_width = (self.box_size.x / self.cell_size).ceil().max(1.0) as usize;
+        self.grid_height = (self.box_size.y / self.cell_size).ceil().max(1.0) as usize;
+
+        self.cells.resize(self.grid_width * self.grid_height, Vec::new());
+        self.cells.truncate(self.grid_width * self.grid_height);
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+
+        for i in 0..self.grains.len() {
+            let (x, y) = self.get_cell(self.grains.positions[i]);
+            self.cells[y * self.grid_width + x].push(i);
+        }
+    }
+
+    fn get_cell(&self, position: Vector2) -> (usize, usize) {
+        let x = (position.x / self.cell_size).floor().clamp(0.0, (self.grid_width - 1) as f32) as usize;
+        let y = (position.y / self.cell_size).floor().clamp(0.0, (self.grid_height - 1) as f32) as usize;
+        (x, y)
+    }
+
+    /// Returns the grains of the cells around grain `i` with an index larger than `i`.
+    fn neighbors(&self, i: usize) -> Vec<usize> {
+        let (x, y) = self.get_cell(self.grains.positions[i]);
+        let mut neighbors = Vec::new();
+
+        for cy in y.saturating_sub(1)..(y + 2).min(self.grid_height) {
+            for cx in x.saturating_sub(1)..(x + 2).min(self.grid_width) {
+                neighbors.extend(self.cells[cy * self.grid_width + cx].iter().filter(|j| **j > i));
+            }
+        }
+
+        neighbors
+    }
+
+    /// Computes the contact forces of a contact of grain `i`.
+    ///
+    /// # Arguments
+    ///
+    /// * `overlap` - Interpenetration depth, positive.
+    /// * `normal` - Unit normal pointing from grain `i` to the other body.
+    /// * `relative_velocity` - Velocity of grain `i` relative to the other body at the contact point.
+    /// * `effective_mass` - Reduced mass of the pair, the mass of grain `i` against a wall.
+    /// * `elongation` - Elongation of the tangential spring at the previous substep.
+    ///
+    /// # Returns
+    ///
+    /// A tuple `(normal_force, tangential_force, elongation)` with the magnitude of the normal force,
+    /// the tangential force applied on grain `i` and the new elongation of the tangential spring.
+    fn calculate_contact_force(&self, overlap: f32, normal: Vector2, relative_velocity: Vector2, effective_mass: f32, elongation: Vector2, dt: f32) -> (f32, Vector2, Vector2) {
+        let damping = self.normal_damping(effective_mass);
+
+        // Spring-dashpot, never pulling the bodies together
+        let normal_velocity = relative_velocity * normal;
+        let normal_force = (self.normal_stiffness * overlap + damping * normal_velocity).max(0.0);
+
+        // Tangential spring, kept in the tangent plane as the contact rotates
+        let tangential_velocity = relative_velocity - normal * normal_velocity;
+        let mut elongation = elongation - normal * (elongation * normal) + tangential_velocity * dt;
+        let tangential_stiffness = self.tangential_stiffness_ratio * self.normal_stiffness;
+        let mut tangential_force = elongation * -tangential_stiffness - tangential_velocity * damping;
+
+        // Coulomb friction: the contact slides and the spring is shortened to the sliding force
+        let max_tangential_force = self.friction * normal_force;
+        let tangential_force_magnitude = tangential_force.magnitude();
+        if tangential_force_magnitude > max_tangential_force {
+            tangential_force = if tangential_force_magnitude > f32::EPSILON {
+                tangential_force * (max_tangential_force / tangential_force_magnitude)
+            } else {
+                Vector2::zero()
+            };
+            elongation = (tangential_force + tangential_velocity * damping) / -tangential_stiffness.max(f32::EPSILON);
+        }
+
+        (normal_force, tangential_force, elongation)
+    }
+
+    /// Torque opposing the relative rolling of a contact, with an effective rolling radius `radius`.
+    fn calculate_rolling_torque(&self, normal_force: f32, radius: f32, relative_angular_velocity: f32) -> f32 {
+        let direction = (relative_angular_velocity / ROLLING_VELOCITY_SCALE).clamp(-1.0, 1.0);
+        -self.rolling_friction * radius * normal_force * direction
+    }
+
+    /// Advances the grains by one substep.
+    fn substep(&mut self, dt: f32) {
+        let grain_count = self.grains.len();
+        let mut forces: Vec<Vector2> = (0..grain_count).map(|i| {
+            let acceleration = Vector2::down() * self.gravity + total_acceleration(&self.force_fields, self.grains.positions[i], self.grains.velocities[i]);
+            acceleration * self.mass(i)
+        }).collect();
+        let mut torques = vec![0.0; grain_count];
+        let mut contacts = HashMap::with_capacity(self.contacts.len());
+
+        self.update_grid();
+
+        for i in 0..grain_count {
+            let position = self.grains.positions[i];
+            let velocity = self.grains.velocities[i];
+            let angular_velocity = self.grains.angular_velocities[i];
+            let radius = self.grains.radii[i];
+
+            // Grain-grain contacts
+            for j in self.neighbors(i) {
+                let offset = self.grains.positions[j] - position;
+                let distance = offset.magnitude();
+                let overlap = radius + self.grains.radii[j] - distance;
+                if overlap <= 0.0 {
+                    continue;
+                }
+
+                let normal = if distance > f32::EPSILON { offset / distance } else { Vector2::up() };
+                let contact_velocity_i = velocity + (normal * radius).perpendicular() * angular_velocity;
+                let contact_velocity_j = self.grains.velocities[j] + (normal * -self.grains.radii[j]).perpendicular() * self.grains.angular_velocities[j];
+                let effective_mass = self.mass(i) * self.mass(j) / (self.mass(i) + self.mass(j));
+                let elongation = self.contacts.get(&(i, j)).copied().unwrap_or(Vector2::zero());
+
+                let (normal_force, tangential_force, elongation) = self.calculate_contact_force(
+                    overlap, normal, contact_velocity_i - contact_velocity_j, effective_mass, elongation, dt
+                );
+                contacts.insert((i, j), elongation);
+
+                let force = normal * -normal_force + tangential_force;
+                forces[i] += force;
+                forces[j] -= force;
+
+                let rolling_radius = radius * self.grains.radii[j] / (radius + self.grains.radii[j]);
+                let rolling_torque = self.calculate_rolling_torque(normal_force, rolling_radius, angular_velocity - self.grains.angular_velocities[j]);
+                torques[i] += (normal * radius).cross(tangential_force) + rolling_torque;
+                torques[j] += (normal * self.grains.radii[j]).cross(tangential_force) - rolling_torque;
+            }
+
+            // Wall contacts, the walls having an infinite mass
+            for (k, segment) in self.segments.iter().enumerate() {
+                let offset = segment.closest_point(position) - position;
+                let distance = offset.magnitude();
+                let overlap = radius - distance;
+                if overlap <= 0.0 || distance <= f32::EPSILON {
+                    continue;
+                }
+
+                let normal = offset / distance;
+                let contact_velocity = velocity + (normal * radius).perpendicular() * angular_velocity;
+                let key = (i, usize::MAX - k);
+                let elongation = self.contacts.get(&key).copied().unwrap_or(Vector2::zero());
+
+                let (normal_force, tangential_force, elongation) = self.calculate_contact_force(
+                    overlap, normal, contact_velocity, self.mass(i), elongation, dt
+                );
+                contacts.insert(key, elongation);
+
+                forces[i] += normal * -normal_force + tangential_force;
+                torques[i] += (normal * radius).cross(tangential_force) + self.calculate_rolling_torque(normal_force, radius, angular_velocity);
+            }
+        }
+
+        self.contacts = contacts;
+
+        // Semi-implicit Euler integration
+        for i in 0..grain_count {
+            let (mass, inertia) = (self.mass(i), self.inertia(i));
+            self.grains.velocities[i] += forces[i] / mass * dt;
+            self.grains.angular_velocities[i] += torques[i] / inertia * dt;
+            self.grains.positions[i] += self.grains.velocities[i] * dt;
+            self.grains.angles[i] += self.grains.angular_velocities[i] * dt;
+        }
+    }
+
+    /// Measures the slope of the pile from the height of its free surface on each side of the summit.
+    fn measure_repose_angle(&self) -> Option<f32> {
+        let bin_width = 4.0 * self.grain_radius;
+        let bin_count = (self.box_size.x / bin_width).ceil().max(1.0) as usize;
+        let mut heights = vec![0.0_f32; bin_count];
+
+        // Only the grains at rest, not the ones being poured
+        for i in 0..self.grains.len() {
+            if self.grains.velocities[i].magnitude() > 10.0 * self.grain_radius {
+                continue;
+            }
+            let bin = ((self.grains.positions[i].x / bin_width).floor().max(0.0) as usize).min(bin_count - 1);
+            heights[bin] = heights[bin].max(self.box_size.y - self.grains.positions[i].y + self.grains.radii[i]);
+        }
+
+        let summit = (0..bin_count).max_by(|a, b| heights[*a].partial_cmp(&heights[*b]).unwrap_or(Ordering::Equal))?;
+        if heights[summit] < 4.0 * self.grain_radius {
+            return None;
+        }
+
+        // Least-squares slope of each flank, from the summit to the foot of the pile
+        let flank_slope = |bins: Vec<usize>| -> Option<f32> {
+            let flank: Vec<(f32, f32)> = bins.into_iter()
+                .take_while(|bin| heights[*bin] > 2.0 * self.grain_radius)
+                .map(|bin| ((bin as f32 + 0.5) * bin_width, heights[bin]))
+                .collect();
+            if flank.len() < 3 {
+                return None;
+            }
+
+            let count = flank.len() as f32;
+            let mean_x = flank.iter().map(|(x, _)| x).sum::<f32>() / count;
+            let mean_height = flank.iter().map(|(_, height)| height).sum::<f32>() / count;
+            let covariance: f32 = flank.iter().map(|(x, height)| (x - mean_x) * (height - mean_height)).sum();
+            let variance: f32 = flank.iter().map(|(x, _)| (x - mean_x) * (x - mean_x)).sum();
+            Some((covariance / variance).abs())
+        };
+
+        let slopes: Vec<f32> = [flank_slope((0..=summit).rev().collect()), flank_slope((summit..bin_count).collect())]
+            .into_iter()
+            .flatten()
+            .collect();
+        if slopes.is_empty() {
+            return None;
+        }
+
+        let slope = slopes.iter().sum::<f32>() / slopes.len() as f32;
+        Some(slope.atan().to_degrees())
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.pour(dt);
+
+        let substep_dt = self.plan_substeps(dt);
+        for _ in 0..self.substeps {
+            self.substep(substep_dt);
+        }
+
+        self.repose_angle = self.measure_repose_angle();
+    }
+}
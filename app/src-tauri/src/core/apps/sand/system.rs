@@ -0,0 +1,104 @@
+use crate::core::app_system::simulation::template::SimulationTemplate;
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::sciences::maths::vector2::Vector2;
+
+use crate::core::sciences::physics::force_fields::handle_force_field_event;
+
+use super::main::Sand;
+use super::data::*;
+
+/// Obligatory implementation of the `SimulationTemplate` trait for the `Sand` struct.
+impl SimulationTemplate for Sand {
+    fn initialize(&mut self, renderer_size: Vector2, serialized_data: Option<String>) -> Result<(), String> {
+        self.clear();
+        self.set_walls(renderer_size, Vec::new());
+
+        let starter_data: StarterData = match serialized_data {
+            Some(data) => match serde_json::from_str(&data) {
+                Ok(deserialized_data) => deserialized_data,
+                Err(e) => return Err(e.to_string())
+            },
+            None => return Ok(())
+        };
+
+        if starter_data.positions.len() > self.max_grains {
+            return Err(format!("Too many grains: {} (maximum {})", starter_data.positions.len(), self.max_grains));
+        }
+
+        self.set_walls(renderer_size, starter_data.segments);
+        for position in starter_data.positions {
+            self.add_grain(position, Vector2::zero());
+        }
+
+        Ok(())
+    }
+
+    fn next_step(&mut self, dt: f32) -> Result<(), String> {
+        self.update(dt);
+
+        Ok(())
+    }
+
+    fn get_data_to_render(&self) -> Result<Box<dyn DataToSend>, String> {
+        let data_to_render = RendererData {
+            grains: self.grains.clone(),
+            segments: self.segments.clone(),
+            substeps: self.substeps,
+            repose_angle: self.repose_angle,
+            force_fields: self.force_fields.clone()
+        };
+
+        Ok(Box::new(data_to_render))
+    }
+
+    fn event_handler(&mut self, event: String, data: Option<String>) -> Result<(), String> {
+        match event.as_str() {
+            "set_settings" => {
+                match data {
+                    Some(data) => {
+                        let settings: EventSettings = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.gravity = settings.gravity;
+                        self.grain_radius = settings.grain_radius.max(0.5);
+                        self.polydispersity = settings.polydispersity.clamp(0.0, 0.9);
+                        self.normal_stiffness = settings.normal_stiffness.max(1.0);
+                        self.restitution = settings.restitution.clamp(0.0, 1.0);
+                        self.friction = settings.friction.max(0.0);
+                        self.rolling_friction = settings.rolling_friction.max(0.0);
+                        self.pour_rate = settings.pour_rate.max(0.0);
+                    },
+                    None => return Err("No data provided for the 'set_settings' event".to_string())
+                }
+            },
+            "pour" => {
+                match data {
+                    Some(data) => {
+                        let pour: EventPour = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.pouring = true;
+                        self.pour_position = pour.position;
+                    },
+                    None => return Err("No data provided for the 'pour' event".to_string())
+                }
+            },
+            "stop_pour" => {
+                self.pouring = false;
+            },
+            "clear" => {
+                self.clear();
+            },
+            "add_force_field" | "remove_force_field" | "clear_force_fields" => {
+                handle_force_field_event(&mut self.force_fields, &event, data)?;
+            },
+            _ => return Err("Unknown event".to_string())
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Serialize, Deserialize};
+
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::sciences::maths::vector2::Vector2;
+use crate::core::sciences::physics::force_fields::ForceField;
+
+/// The smoke density as an RGBA image of `width` × `height` pixels, one pixel per cell.
+#[derive(Serialize, Clone)]
+pub struct RendererData {
+    pub width: usize,
+    pub height: usize,
+    pub cell_size: f32,
+    pub pixels: Vec<u8>,
+    pub force_fields: Vec<ForceField>,
+}
+
+impl DataToSend for RendererData {}
+
+#[derive(Deserialize, Clone)]
+pub struct StarterData {
+    pub cell_size: f32
+}
+
+#[derive(Deserialize)]
+pub struct EventSettings {
+    pub viscosity: f32,
+    pub diffusion: f32,
+    pub dissipation: f32,
+    pub vorticity_strength: f32,
+    pub buoyancy: f32,
+    pub solver_iterations: usize,
+    pub injection_radius: f32,
+    pub injection_amount: f32
+}
+
+/// Smoke and velocity injected under the pointer.
+#[derive(Deserialize)]
+pub struct EventInjection {
+    pub position: Vector2,
+    pub velocity: Vector2
+}
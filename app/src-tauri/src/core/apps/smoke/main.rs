@@ -0,0 +1,341 @@
+use colorgrad::Gradient;
+
+use crate::core::sciences::maths::Vector2;
+use crate::core::sciences::physics::force_fields::{ForceField, total_acceleration};
+
+/// How a field behaves on the walls of the grid.
+#[derive(Clone, Copy, PartialEq)]
+enum BoundaryKind {
+    /// Scalar field, copied from the neighbouring cell.
+    Scalar,
+    /// Horizontal velocity, reflected on the left and right walls.
+    VelocityX,
+    /// Vertical velocity, reflected on the top and bottom walls.
+    VelocityY,
+}
+
+/// Smoke simulated on an Eulerian grid with the stable fluids method of Stam (1999).
+///
+/// Velocities and smoke density are stored at the cell centers, in row-major order,
+/// the outer ring of cells being used for the boundary conditions. Each step adds the
+/// injected smoke and forces, then diffuses, advects (semi-Lagrangian) and projects the
+/// velocity to keep it divergence free, before advecting the density with it.
+pub struct Smoke {
+    // GRID PROPERTIES
+    pub width: usize,
+    pub height: usize,
+    pub cell_size: f32,
+    pub velocities_x: Vec<f32>,
+    pub velocities_y: Vec<f32>,
+    pub densities: Vec<f32>,
+    // SMOKE PROPERTIES
+    pub viscosity: f32,
+    pub diffusion: f32,
+    pub dissipation: f32,
+    pub vorticity_strength: f32,
+    pub buoyancy: f32,
+    /// External force fields acting on the air.
+    pub force_fields: Vec<ForceField>,
+    pub solver_iterations: usize,
+    // INTERACTION PROPERTIES
+    pub injection: bool,
+    pub injection_position: Vector2,
+    pub injection_velocity: Vector2,
+    pub injection_radius: f32,
+    pub injection_amount: f32,
+    // OTHER PROPERTIES
+    pub density_gradient: Gradient
+}
+
+impl Smoke {
+    pub fn new(density_gradient: Gradient) -> Self {
+        Smoke {
+            // GRID PROPERTIES
+            width: 0,
+            height: 0,
+            cell_size: 8.0,
+            velocities_x: Vec::new(),
+            velocities_y: Vec::new(),
+            densities: Vec::new(),
+            // SMOKE PROPERTIES
+            viscosity: 0.0,
+            diffusion: 0.0,
+            dissipation: 0.1,
+            vorticity_strength: 10.0,
+            buoyancy: 50.0,
+            force_fields: Vec::new(),
+            solver_iterations: 20,
+            // INTERACTION PROPERTIES
+            injection: false,
+            injection_position: Vector2::zero(),
+            injection_velocity: Vector2::zero(),
+            injection_radius: 30.0,
+            injection_amount: 5.0,
+            // OTHER PROPERTIES
+            density_gradient
+        }
+    }
+
+    /// Allocates an empty grid covering `size` with cells of `cell_size` pixels, plus the boundary ring.
+    pub fn resize(&mut self, size: Vector2, cell_size: f32) {
+        self.cell_size = cell_size.max(1.0);
+        self.width = (size.x / self.cell_size).ceil().max(1.0) as usize + 2;
+        self.height = (size.y / self.cell_size).ceil().max(1.0) as usize + 2;
+        self.clear();
+    }
+
+    /// Removes all the smoke and stops every motion.
+    pub fn clear(&mut self) {
+        let cell_count = self.width * self.height;
+        self.velocities_x = vec![0.0; cell_count];
+        self.velocities_y = vec![0.0; cell_count];
+        self.densities = vec![0.0; cell_count];
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        x + y * self.width
+    }
+
+    /// Returns the position of the center of the cell `(x, y)` in pixels, the boundary ring being outside of the renderer.
+    fn cell_center(&self, x: usize, y: usize) -> Vector2 {
+        Vector2::new((x as f32 - 0.5) * self.cell_size, (y as f32 - 0.5) * self.cell_size)
+    }
+
+    /// Applies the boundary conditions of `kind` to `field` on the outer ring of cells.
+    fn set_boundary(&self, kind: BoundaryKind, field: &mut [f32]) {
+        let (w, h) = (self.width, self.height);
+
+        for y in 1..h - 1 {
+            let sign = if kind == BoundaryKind::VelocityX { -1.0 } else { 1.0 };
+            field[self.index(0, y)] = sign * field[self.index(1, y)];
+            field[self.index(w - 1, y)] = sign * field[self.index(w - 2, y)];
+        }
+
+        for x in 1..w - 1 {
+            let sign = if kind == BoundaryKind::VelocityY { -1.0 } else { 1.0 };
+            field[self.index(x, 0)] = sign * field[self.index(x, 1)];
+            field[self.index(x, h - 1)] = sign * field[self.index(x, h - 2)];
+        }
+
+        field[self.index(0, 0)] = 0.5 * (field[self.index(1, 0)] + field[self.index(0, 1)]);
+        field[self.index(0, h - 1)] = 0.5 * (field[self.index(1, h - 1)] + field[self.index(0, h - 2)]);
+        field[self.index(w - 1, 0)] = 0.5 * (field[self.index(w - 2, 0)] + field[self.index(w - 1, 1)]);
+        field[self.index(w - 1, h - 1)] = 0.5 * (field[self.index(w - 2, h - 1)] + field[self.index(w - 1, h - 2)]);
+    }
+
+    /// Solves `x - a ∇²x = x0` with Gauss-Seidel iterations, `a` being expressed in cells.
+    fn solve_linear_system(&self, kind: BoundaryKind, field: &mut [f32], initial: &[f32], a: f32, c: f32) {
+        for _ in 0..self.solver_iterations {
+            for y in 1..self.height - 1 {
+                for x in 1..self.width - 1 {
+                    let neighbors = field[self.index(x - 1, y)] + field[self.index(x + 1, y)] + field[self.index(x, y - 1)] + field[self.index(x, y + 1)];
+                    field[self.index(x, y)] = (initial[self.index(x, y)] + a * neighbors) / c;
+                }
+            }
+            self.set_boundary(kind, field);
+        }
+    }
+
+    /// Diffuses `field` implicitly with the coefficient `rate`, in pixels² per second.
+    fn diffuse(&self, kind: BoundaryKind, field: &mut [f32], rate: f32, dt: f32) {
+        if rate <= 0.0 {
+            return;
+        }
+
+        let initial = field.to_vec();
+        let a = dt * rate / (self.cell_size * self.cell_size);
+        self.solve_linear_system(kind, field, &initial, a, 1.0 + 4.0 * a);
+    }
+
+    /// Samples `field` at the fractional cell coordinates `(x, y)` with bilinear interpolation.
+    fn sample(&self, field: &[f32], x: f32, y: f32) -> f32 {
+        let x = x.clamp(0.5, self.width as f32 - 1.5);
+        let y = y.clamp(0.5, self.height as f32 - 1.5);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+
+        let top = field[self.index(x0, y0)] * (1.0 - tx) + field[self.index(x0 + 1, y0)] * tx;
+        let bottom = field[self.index(x0, y0 + 1)] * (1.0 - tx) + field[self.index(x0 + 1, y0 + 1)] * tx;
+
+        top * (1.0 - ty) + bottom * ty
+    }
+
+    /// Moves `field` along the velocity with a semi-Lagrangian backtrace.
+    fn advect(&self, kind: BoundaryKind, field: &[f32], velocities_x: &[f32], velocities_y: &[f32], dt: f32) -> Vec<f32> {
+        let mut advected = vec![0.0; field.len()];
+        let dt_cells = dt / self.cell_size;
+
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                let i = self.index(x, y);
+                let origin_x = x as f32 - dt_cells * velocities_x[i];
+                let origin_y = y as f32 - dt_cells * velocities_y[i];
+                advected[i] = self.sample(field, origin_x, origin_y);
+            }
+        }
+
+        self.set_boundary(kind, &mut advected);
+        advected
+    }
+
+    /// Removes the divergence of the velocity by solving the pressure Poisson equation.
+    fn project(&mut self) {
+        let cell_count = self.width * self.height;
+        let mut divergence = vec![0.0; cell_count];
+        let mut pressure = vec![0.0; cell_count];
+
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                divergence[self.index(x, y)] = -0.5 * (
+                    self.velocities_x[self.index(x + 1, y)] - self.velocities_x[self.index(x - 1, y)]
+                    + self.velocities_y[self.index(x, y + 1)] - self.velocities_y[self.index(x, y - 1)]
+                );
+            }
+        }
+
+        self.set_boundary(BoundaryKind::Scalar, &mut divergence);
+        self.solve_linear_system(BoundaryKind::Scalar, &mut pressure, &divergence, 1.0, 4.0);
+
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                let i = self.index(x, y);
+                self.velocities_x[i] -= 0.5 * (pressure[self.index(x + 1, y)] - pressure[self.index(x - 1, y)]);
+                self.velocities_y[i] -= 0.5 * (pressure[self.index(x, y + 1)] - pressure[self.index(x, y - 1)]);
+            }
+        }
+
+        let (mut velocities_x, mut velocities_y) = (std::mem::take(&mut self.velocities_x), std::mem::take(&mut self.velocities_y));
+        self.set_boundary(BoundaryKind::VelocityX, &mut velocities_x);
+        self.set_boundary(BoundaryKind::VelocityY, &mut velocities_y);
+        self.velocities_x = velocities_x;
+        self.velocities_y = velocities_y;
+    }
+
+    /// Adds smoke and velocity around the pointer, fading out towards the injection radius.
+    fn inject(&mut self, dt: f32) {
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                let distance = self.cell_center(x, y).distance_to(self.injection_position);
+                if distance >= self.injection_radius {
+                    continue;
+                }
+
+                let i = self.index(x, y);
+                let influence = 1.0 - distance / self.injection_radius;
+                self.densities[i] += self.injection_amount * influence * dt;
+                // Relax the velocity towards the pointer velocity, like the interactive force of the fluid
+                self.velocities_x[i] += (self.injection_velocity.x - self.velocities_x[i]) * influence;
+                self.velocities_y[i] += (self.injection_velocity.y - self.velocities_y[i]) * influence;
+            }
+        }
+    }
+
+    /// Applies the force fields, the buoyancy, which makes dense smoke rise, and the vorticity confinement,
+    /// which restores the small swirls smoothed out by the numerical diffusion (Fedkiw et al. 2001).
+    fn apply_forces(&mut self, dt: f32) {
+        let cell_count = self.width * self.height;
+
+        for y in 1..self.height.saturating_sub(1) {
+            for x in 1..self.width.saturating_sub(1) {
+                let i = self.index(x, y);
+                let velocity = Vector2::new(self.velocities_x[i], self.velocities_y[i]);
+                let acceleration = total_acceleration(&self.force_fields, self.cell_center(x, y), velocity);
+                self.velocities_x[i] += acceleration.x * dt;
+                self.velocities_y[i] += acceleration.y * dt;
+            }
+        }
+
+        // The y axis points down
+        if self.buoyancy != 0.0 {
+            for i in 0..cell_count {
+                self.velocities_y[i] -= self.buoyancy * self.densities[i] * dt;
+            }
+        }
+
+        if self.vorticity_strength <= 0.0 {
+            return;
+        }
+
+        let mut curl = vec![0.0; cell_count];
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                curl[self.index(x, y)] = 0.5 * (
+                    self.velocities_y[self.index(x + 1, y)] - self.velocities_y[self.index(x - 1, y)]
+                    - self.velocities_x[self.index(x, y + 1)] + self.velocities_x[self.index(x, y - 1)]
+                ) / self.cell_size;
+            }
+        }
+
+        for y in 2..self.height.saturating_sub(2) {
+            for x in 2..self.width.saturating_sub(2) {
+                let i = self.index(x, y);
+                let gradient = Vector2::new(
+                    0.5 * (curl[self.index(x + 1, y)].abs() - curl[self.index(x - 1, y)].abs()),
+                    0.5 * (curl[self.index(x, y + 1)].abs() - curl[self.index(x, y - 1)].abs())
+                );
+                let length = gradient.magnitude();
+                if length <= f32::EPSILON {
+                    continue;
+                }
+
+                let normal = gradient / length;
+                let force = Vector2::new(normal.y, -normal.x) * (curl[i] * self.vorticity_strength * self.cell_size);
+                self.velocities_x[i] += force.x * dt;
+                self.velocities_y[i] += force.y * dt;
+            }
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.width < 3 || self.height < 3 || dt <= 0.0 {
+            return;
+        }
+
+        if self.injection {
+            self.inject(dt);
+        }
+
+        self.apply_forces(dt);
+
+        // Velocity step
+        let (mut velocities_x, mut velocities_y) = (std::mem::take(&mut self.velocities_x), std::mem::take(&mut self.velocities_y));
+        self.diffuse(BoundaryKind::VelocityX, &mut velocities_x, self.viscosity, dt);
+        self.diffuse(BoundaryKind::VelocityY, &mut velocities_y, self.viscosity, dt);
+        self.velocities_x = velocities_x;
+        self.velocities_y = velocities_y;
+        self.project();
+
+        let velocities_x = self.advect(BoundaryKind::VelocityX, &self.velocities_x, &self.velocities_x, &self.velocities_y, dt);
+        let velocities_y = self.advect(BoundaryKind::VelocityY, &self.velocities_y, &self.velocities_x, &self.velocities_y, dt);
+        self.velocities_x = velocities_x;
+        self.velocities_y = velocities_y;
+        self.project();
+
+        // Density step
+        let mut densities = std::mem::take(&mut self.densities);
+        self.diffuse(BoundaryKind::Scalar, &mut densities, self.diffusion, dt);
+        let mut densities = self.advect(BoundaryKind::Scalar, &densities, &self.velocities_x, &self.velocities_y, dt);
+        let decay = 1.0 / (1.0 + self.dissipation * dt);
+        densities.iter_mut().for_each(|density| *density *= decay);
+        self.densities = densities;
+    }
+
+    /// Converts the smoke density of the inner cells into an RGBA image through the density gradient.
+    pub fn to_image(&self) -> Vec<u8> {
+        if self.width < 3 || self.height < 3 {
+            return Vec::new();
+        }
+
+        let mut pixels = Vec::with_capacity((self.width - 2) * (self.height - 2) * 4);
+
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                let density = self.densities[self.index(x, y)].clamp(0.0, 1.0);
+                let [r, g, b, _] = self.density_gradient.at(density as f64).to_rgba8();
+                pixels.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+
+        pixels
+    }
+}
@@ -0,0 +1,95 @@
+use crate::core::app_system::simulation::template::SimulationTemplate;
+use crate::core::app_system::simulation::renderer::DataToSend;
+use crate::core::sciences::maths::vector2::Vector2;
+
+use crate::core::sciences::physics::force_fields::handle_force_field_event;
+
+use super::main::Smoke;
+use super::data::*;
+
+/// Obligatory implementation of the `SimulationTemplate` trait for the `Smoke` struct.
+impl SimulationTemplate for Smoke {
+    fn initialize(&mut self, renderer_size: Vector2, serialized_data: Option<String>) -> Result<(), String> {
+        let cell_size = match serialized_data {
+            Some(data) => match serde_json::from_str::<StarterData>(&data) {
+                Ok(deserialized_data) => deserialized_data.cell_size,
+                Err(e) => return Err(e.to_string())
+            },
+            None => self.cell_size
+        };
+
+        self.resize(renderer_size, cell_size);
+
+        Ok(())
+    }
+
+    fn next_step(&mut self, dt: f32) -> Result<(), String> {
+        self.update(dt);
+
+        Ok(())
+    }
+
+    fn get_data_to_render(&self) -> Result<Box<dyn DataToSend>, String> {
+        let data_to_render = RendererData {
+            width: self.width.saturating_sub(2),
+            height: self.height.saturating_sub(2),
+            cell_size: self.cell_size,
+            pixels: self.to_image(),
+            force_fields: self.force_fields.clone()
+        };
+
+        Ok(Box::new(data_to_render))
+    }
+
+    fn event_handler(&mut self, event: String, data: Option<String>) -> Result<(), String> {
+        match event.as_str() {
+            "set_settings" => {
+                match data {
+                    Some(data) => {
+                        let settings: EventSettings = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.viscosity = settings.viscosity;
+                        self.diffusion = settings.diffusion;
+                        self.dissipation = settings.dissipation;
+                        self.vorticity_strength = settings.vorticity_strength;
+                        self.buoyancy = settings.buoyancy;
+                        self.solver_iterations = settings.solver_iterations;
+                        self.injection_radius = settings.injection_radius;
+                        self.injection_amount = settings.injection_amount;
+                    },
+                    None => return Err("No data provided for the 'set_settings' event".to_string())
+                }
+            },
+            "inject" => {
+                match data {
+                    Some(data) => {
+                        let injection: EventInjection = match serde_json::from_str(&data) {
+                            Ok(deserialized_data) => deserialized_data,
+                            Err(e) => return Err(e.to_string())
+                        };
+
+                        self.injection = true;
+                        self.injection_position = injection.position;
+                        self.injection_velocity = injection.velocity;
+                    },
+                    None => return Err("No data provided for the 'inject' event".to_string())
+                }
+            },
+            "stop_injection" => {
+                self.injection = false;
+            },
+            "clear" => {
+                self.clear();
+            },
+            "add_force_field" | "remove_force_field" | "clear_force_fields" => {
+                handle_force_field_event(&mut self.force_fields, &event, data)?;
+            },
+            _ => return Err("Unknown event".to_string())
+        }
+
+        Ok(())
+    }
+}
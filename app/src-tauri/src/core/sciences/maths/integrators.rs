@@ -0,0 +1,248 @@
+use serde::{Serialize, Deserialize};
+
+use super::Vector2;
+
+/// Relative tolerance of the adaptive RK45 scheme when it is used through `Integrator::step`.
+pub const DEFAULT_RK45_TOLERANCE: f32 = 1e-5;
+
+/// Numerical scheme advancing a body of given position and velocity under an acceleration.
+///
+/// The acceleration is a function of the position and the velocity. The schemes do not conserve
+/// the energy in the same way, which can be seen on an orbit or a bouncing ball without losses:
+/// explicit Euler gains energy, RK4 slowly loses it, and the symplectic ones (semi-implicit Euler,
+/// velocity Verlet) make it oscillate without drifting.
+///
+/// Serialized in snake case, e.g. `"velocity_verlet"`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Integrator {
+    /// First order, the velocity and the position are updated from the start of the step.
+    ExplicitEuler,
+    /// First order and symplectic, the position is updated with the new velocity.
+    #[default]
+    SemiImplicitEuler,
+    /// Second order and symplectic, two acceleration evaluations per step.
+    VelocityVerlet,
+    /// Classic fourth order Runge–Kutta, four acceleration evaluations per step.
+    Rk4,
+    /// Dormand–Prince 5(4), substepping until the estimated error is below `DEFAULT_RK45_TOLERANCE`.
+    Rk45,
+}
+
+impl Integrator {
+    /// Advances `position` and `velocity` by `dt` under `acceleration(position, velocity)`.
+    ///
+    /// # Returns
+    ///
+    /// The new position and velocity.
+    pub fn step<F>(&self, position: Vector2, velocity: Vector2, dt: f32, acceleration: F) -> (Vector2, Vector2)
+    where F: Fn(Vector2, Vector2) -> Vector2 {
+        match self {
+            Integrator::ExplicitEuler => explicit_euler(position, velocity, dt, acceleration),
+            Integrator::SemiImplicitEuler => semi_implicit_euler(position, velocity, dt, acceleration),
+            Integrator::VelocityVerlet => velocity_verlet(position, velocity, dt, acceleration),
+            Integrator::Rk4 => rk4(position, velocity, dt, acceleration),
+            Integrator::Rk45 => rk45(position, velocity, dt, DEFAULT_RK45_TOLERANCE, acceleration),
+        }
+    }
+}
+
+pub fn explicit_euler<F>(position: Vector2, velocity: Vector2, dt: f32, acceleration: F) -> (Vector2, Vector2)
+where F: Fn(Vector2, Vector2) -> Vector2 {
+    let acceleration = acceleration(position, velocity);
+    (position + velocity * dt, velocity + acceleration * dt)
+}
+
+pub fn semi_implicit_euler<F>(position: Vector2, velocity: Vector2, dt: f32, acceleration: F) -> (Vector2, Vector2)
+where F: Fn(Vector2, Vector2) -> Vector2 {
+    let new_velocity = velocity + acceleration(position, velocity) * dt;
+    (position + new_velocity * dt, new_velocity)
+}
+
+/// Velocity Verlet, exact for a constant acceleration.
+///
+/// The acceleration at the end of the step is evaluated with the half step velocity,
+/// which keeps the scheme explicit when the acceleration depends on the velocity (drag, wind...).
+pub fn velocity_verlet<F>(position: Vector2, velocity: Vector2, dt: f32, acceleration: F) -> (Vector2, Vector2)
+where F: Fn(Vector2, Vector2) -> Vector2 {
+    let half_velocity = velocity + acceleration(position, velocity) * (0.5 * dt);
+    let new_position = position + half_velocity * dt;
+    let new_velocity = half_velocity + acceleration(new_position, half_velocity) * (0.5 * dt);
+    (new_position, new_velocity)
+}
+
+pub fn rk4<F>(position: Vector2, velocity: Vector2, dt: f32, acceleration: F) -> (Vector2, Vector2)
+where F: Fn(Vector2, Vector2) -> Vector2 {
+    let k1_position = velocity;
+    let k1_velocity = acceleration(position, velocity);
+
+    let k2_position = velocity + k1_velocity * (0.5 * dt);
+    let k2_velocity = acceleration(position + k1_position * (0.5 * dt), k2_position);
+
+    let k3_position = velocity + k2_velocity * (0.5 * dt);
+    let k3_velocity = acceleration(position + k2_position * (0.5 * dt), k3_position);
+
+    let k4_position = velocity + k3_velocity * dt;
+    let k4_velocity = acceleration(position + k3_position * dt, k4_position);
+
+    (
+        position + (k1_position + k2_position * 2.0 + k3_position * 2.0 + k4_position) * (dt / 6.0),
+        velocity + (k1_velocity + k2_velocity * 2.0 + k3_velocity * 2.0 + k4_velocity) * (dt / 6.0)
+    )
+}
+
+/// Dormand–Prince coefficients of the stages, `DORMAND_PRINCE_A[i][j]` weighting the stage `j < i` in the stage `i`.
+const DORMAND_PRINCE_A: [[f32; 6]; 7] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+    [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+    [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+/// Weights of the fifth order solution, the one that is kept.
+const DORMAND_PRINCE_B5: [f32; 7] = [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0, 0.0];
+/// Weights of the embedded fourth order solution, only used to estimate the error.
+const DORMAND_PRINCE_B4: [f32; 7] = [5179.0 / 57600.0, 0.0, 7571.0 / 16695.0, 393.0 / 640.0, -92097.0 / 339200.0, 187.0 / 2100.0, 1.0 / 40.0];
+
+/// Adaptive Dormand–Prince 5(4), covering `dt` with as many substeps as needed for the estimated
+/// error to stay below `tolerance`, relative to the magnitude of the position and the velocity.
+///
+/// The substep starts at `dt` on every call, and cannot shrink below `dt / 1024` so that a stiff
+/// acceleration cannot stall the simulation. If the error stops being finite, the integration stops
+/// at the last accepted substep, the returned state then covering less than `dt`.
+pub fn rk45<F>(mut position: Vector2, mut velocity: Vector2, dt: f32, tolerance: f32, acceleration: F) -> (Vector2, Vector2)
+where F: Fn(Vector2, Vector2) -> Vector2 {
+    let min_substep = dt / 1024.0;
+    let mut time = 0.0;
+    let mut substep = dt;
+
+    // Stop short of rounding errors, which could leave an endless remainder of the step
+    while dt - time > dt * 1e-6 {
+        substep = substep.min(dt - time).max(min_substep.min(dt - time));
+
+        let mut k_position = [Vector2::zero(); 7];
+        let mut k_velocity = [Vector2::zero(); 7];
+        for stage in 0..7 {
+            let mut stage_position = position;
+            let mut stage_velocity = velocity;
+            for previous in 0..stage {
+                stage_position += k_position[previous] * (substep * DORMAND_PRINCE_A[stage][previous]);
+                stage_velocity += k_velocity[previous] * (substep * DORMAND_PRINCE_A[stage][previous]);
+            }
+
+            k_position[stage] = stage_velocity;
+            k_velocity[stage] = acceleration(stage_position, stage_velocity);
+        }
+
+        let mut new_position = position;
+        let mut new_velocity = velocity;
+        let mut position_error = Vector2::zero();
+        let mut velocity_error = Vector2::zero();
+        for stage in 0..7 {
+            new_position += k_position[stage] * (substep * DORMAND_PRINCE_B5[stage]);
+            new_velocity += k_velocity[stage] * (substep * DORMAND_PRINCE_B5[stage]);
+            position_error += k_position[stage] * (substep * (DORMAND_PRINCE_B5[stage] - DORMAND_PRINCE_B4[stage]));
+            velocity_error += k_velocity[stage] * (substep * (DORMAND_PRINCE_B5[stage] - DORMAND_PRINCE_B4[stage]));
+        }
+
+        let position_error = position_error.magnitude() / (tolerance * (1.0 + new_position.magnitude()));
+        let velocity_error = velocity_error.magnitude() / (tolerance * (1.0 + new_velocity.magnitude()));
+        // An acceleration overflowing to infinity or NaN gives no usable error, and no substep could fix it:
+        // the state reached so far is kept rather than retrying forever
+        if !position_error.is_finite() || !velocity_error.is_finite() {
+            break;
+        }
+        let error = position_error.max(velocity_error);
+
+        if error <= 1.0 || substep <= min_substep {
+            time += substep;
+            position = new_position;
+            velocity = new_velocity;
+        }
+
+        // Usual step size control of the embedded methods, with a safety factor
+        let factor = if error > 0.0 { 0.9 * error.powf(-0.2) } else { 5.0 };
+        substep *= factor.clamp(0.2, 5.0);
+    }
+
+    (position, velocity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Harmonic oscillator of unit angular frequency, starting at rest at `x = 1`.
+    fn oscillator_acceleration(position: Vector2, _velocity: Vector2) -> Vector2 {
+        position * -1.0
+    }
+
+    fn oscillator_energy(position: Vector2, velocity: Vector2) -> f32 {
+        0.5 * (position.magnitude_squared() + velocity.magnitude_squared())
+    }
+
+    const OSCILLATOR_DT: f32 = 0.05;
+    /// About 10 periods.
+    const OSCILLATOR_STEPS: usize = 1257;
+
+    /// Integrates the oscillator, returning the final state and the largest relative energy deviation.
+    fn run_oscillator(integrator: Integrator) -> (Vector2, Vector2, f32) {
+        let (mut position, mut velocity) = (Vector2::new(1.0, 0.0), Vector2::zero());
+        let initial_energy = oscillator_energy(position, velocity);
+
+        let mut max_deviation: f32 = 0.0;
+        for _ in 0..OSCILLATOR_STEPS {
+            (position, velocity) = integrator.step(position, velocity, OSCILLATOR_DT, oscillator_acceleration);
+            max_deviation = max_deviation.max((oscillator_energy(position, velocity) / initial_energy - 1.0).abs());
+        }
+
+        (position, velocity, max_deviation)
+    }
+
+    #[test]
+    fn explicit_euler_gains_energy() {
+        let (position, velocity, _) = run_oscillator(Integrator::ExplicitEuler);
+        assert!(oscillator_energy(position, velocity) > 2.0 * 0.5);
+    }
+
+    #[test]
+    fn symplectic_schemes_keep_the_energy_bounded() {
+        let (_, _, semi_implicit_deviation) = run_oscillator(Integrator::SemiImplicitEuler);
+        assert!(semi_implicit_deviation < 0.05, "semi-implicit Euler deviation {}", semi_implicit_deviation);
+
+        let (_, _, verlet_deviation) = run_oscillator(Integrator::VelocityVerlet);
+        assert!(verlet_deviation < 1e-3, "velocity Verlet deviation {}", verlet_deviation);
+    }
+
+    #[test]
+    fn runge_kutta_schemes_follow_the_exact_solution() {
+        let time = (OSCILLATOR_STEPS as f64 * OSCILLATOR_DT as f64) as f32;
+        let (exact_position, exact_velocity) = (Vector2::new(time.cos(), 0.0), Vector2::new(-time.sin(), 0.0));
+
+        for integrator in [Integrator::Rk4, Integrator::Rk45] {
+            let (position, velocity, deviation) = run_oscillator(integrator);
+            assert!(position.distance_to(exact_position) < 1e-4, "{:?} position {:?}", integrator, position);
+            assert!(velocity.distance_to(exact_velocity) < 1e-4, "{:?} velocity {:?}", integrator, velocity);
+            assert!(deviation < 1e-4, "{:?} deviation {}", integrator, deviation);
+        }
+    }
+
+    #[test]
+    fn rk45_is_exact_for_a_constant_acceleration() {
+        let (position, velocity, acceleration, time) = (Vector2::new(1.0, 2.0), Vector2::new(3.0, 4.0), Vector2::new(0.0, -10.0), 1.5);
+        let (new_position, new_velocity) = rk45(position, velocity, time, DEFAULT_RK45_TOLERANCE, |_, _| acceleration);
+
+        assert!(new_position.distance_to(position + velocity * time + acceleration * (0.5 * time * time)) < 1e-4);
+        assert!(new_velocity.distance_to(velocity + acceleration * time) < 1e-4);
+    }
+
+    #[test]
+    fn rk45_returns_on_a_non_finite_acceleration() {
+        let (position, velocity) = (Vector2::new(1.0, 2.0), Vector2::new(3.0, 4.0));
+        let (new_position, new_velocity) = rk45(position, velocity, 0.1, DEFAULT_RK45_TOLERANCE, |_, _| Vector2::new(f32::NAN, 0.0));
+
+        assert_eq!((new_position, new_velocity), (position, velocity));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod vector2;
+pub mod integrators;
 
 #[allow(unused_imports)]
 pub use vector2::*;
\ No newline at end of file
@@ -156,6 +156,28 @@ impl Vector2 {
   pub fn direction_to(&self, other: Vector2) -> Result<Vector2, String> {
     (*self - other).normalize()
   }
+
+  /// Retourne le vecteur tourné d'un angle donné (en radians)
+  pub fn rotate(&self, angle: f32) -> Vector2 {
+    let (sin, cos) = angle.sin_cos();
+    Vector2 {
+      x: self.x * cos - self.y * sin,
+      y: self.x * sin + self.y * cos,
+    }
+  }
+
+  /// Retourne le vecteur perpendiculaire (rotation de 90°)
+  pub fn perpendicular(&self) -> Vector2 {
+    Vector2 {
+      x: -self.y,
+      y: self.x,
+    }
+  }
+
+  /// Retourne le produit vectoriel 2D (composante z) entre deux vecteurs
+  pub fn cross(&self, other: Vector2) -> f32 {
+    self.x * other.y - self.y * other.x
+  }
 }
 
 /// Implementation de l'addition
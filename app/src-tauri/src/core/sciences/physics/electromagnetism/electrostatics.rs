@@ -0,0 +1,137 @@
+use serde::{Serialize, Deserialize};
+
+use crate::core::sciences::maths::Vector2;
+
+/// Point charge creating an electrostatic field.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PointCharge {
+    pub position: Vector2,
+    pub charge: f32,
+}
+
+/// Electric potential and field sampled at the centers of the cells of a grid covering the renderer.
+#[derive(Serialize, Clone, Default)]
+pub struct FieldGrid {
+    pub width: usize,
+    pub height: usize,
+    pub cell_size: f32,
+    pub potentials: Vec<f32>,
+    pub fields: Vec<Vector2>,
+}
+
+/// Electrostatic field created by point charges in a uniform external field.
+///
+/// The Coulomb interactions are softened by a length `softening`, the potential of a charge `q` at
+/// distance `r` being `k q / sqrt(r² + softening²)`, so that the field stays finite on the charges.
+/// The potential of the external field is taken as zero at the origin.
+#[derive(Clone, Copy, Debug)]
+pub struct ElectrostaticField {
+    pub coulomb_constant: f32,
+    pub softening: f32,
+    pub external_field: Vector2,
+}
+
+impl ElectrostaticField {
+    /// Electric field at `position`, the charges lying exactly there excluded.
+    pub fn field(&self, charges: &[PointCharge], position: Vector2) -> Vector2 {
+        let softening_squared = self.softening * self.softening;
+
+        charges.iter().fold(self.external_field, |field, source| {
+            let offset = position - source.position;
+            let distance_squared = offset.magnitude_squared();
+            if distance_squared <= f32::EPSILON {
+                return field;
+            }
+
+            let softened_squared = distance_squared + softening_squared;
+            field + offset * (self.coulomb_constant * source.charge / (softened_squared * softened_squared.sqrt()))
+        })
+    }
+
+    /// Electric potential at `position`, the charges lying exactly there excluded.
+    pub fn potential(&self, charges: &[PointCharge], position: Vector2) -> f32 {
+        let softening_squared = self.softening * self.softening;
+
+        charges.iter().fold(-(self.external_field * position), |potential, source| {
+            let distance_squared = position.distance_to_squared(source.position);
+            if distance_squared <= f32::EPSILON {
+                return potential;
+            }
+
+            potential + self.coulomb_constant * source.charge / (distance_squared + softening_squared).sqrt()
+        })
+    }
+
+    /// Samples the potential and the field on a grid of cells of `cell_size` covering `size`.
+    pub fn field_grid(&self, charges: &[PointCharge], size: Vector2, cell_size: f32) -> FieldGrid {
+        let cell_size = cell_size.max(1.0);
+        let width = (size.x / cell_size).ceil().max(1.0) as usize;
+        let height = (size.y / cell_size).ceil().max(1.0) as usize;
+
+        let mut grid = FieldGrid { width, height, cell_size, potentials: Vec::with_capacity(width * height), fields: Vec::with_capacity(width * height) };
+        for y in 0..height {
+            for x in 0..width {
+                let center = Vector2::new((x as f32 + 0.5) * cell_size, (y as f32 + 0.5) * cell_size);
+                grid.potentials.push(self.potential(charges, center));
+                grid.fields.push(self.field(charges, center));
+            }
+        }
+
+        grid
+    }
+
+    /// Traces field lines in the rectangle from the origin to `size`.
+    ///
+    /// The lines start around the charges of the sign carrying the most charge, `lines_per_charge`
+    /// lines for the largest charge and proportionally fewer for the others (Gauss's law), and follow
+    /// the field, backwards from the negative charges, with midpoint steps of `step_length`. A line stops
+    /// on a charge of the other sign, out of the rectangle, or after `max_steps` steps.
+    pub fn field_lines(&self, charges: &[PointCharge], size: Vector2, lines_per_charge: usize, step_length: f32, max_steps: usize) -> Vec<Vec<Vector2>> {
+        let positive_charge: f32 = charges.iter().filter(|source| source.charge > 0.0).map(|source| source.charge).sum();
+        let negative_charge: f32 = charges.iter().filter(|source| source.charge < 0.0).map(|source| -source.charge).sum();
+        let sign = if positive_charge >= negative_charge { 1.0 } else { -1.0 };
+        let max_charge = charges.iter().map(|source| source.charge * sign).fold(0.0, f32::max);
+        if max_charge <= 0.0 || step_length <= 0.0 {
+            return Vec::new();
+        }
+
+        let start_radius = self.softening.max(step_length);
+        let is_inside = |position: Vector2| position.x >= 0.0 && position.y >= 0.0 && position.x <= size.x && position.y <= size.y;
+        // Normalized direction along which the line goes, zero where the field vanishes
+        let direction = |position: Vector2| (self.field(charges, position) * sign).normalize().unwrap_or(Vector2::zero());
+
+        let mut lines = Vec::new();
+        for source in charges.iter().filter(|source| source.charge * sign > 0.0) {
+            let line_count = ((lines_per_charge as f32 * source.charge * sign / max_charge).round() as usize).max(1);
+
+            for k in 0..line_count {
+                let angle = 2.0 * std::f32::consts::PI * (k as f32 + 0.5) / line_count as f32;
+                let mut position = source.position + Vector2::new(angle.cos(), angle.sin()) * start_radius;
+                let mut line = vec![source.position, position];
+
+                for _ in 0..max_steps {
+                    let half_step = position + direction(position) * (0.5 * step_length);
+                    let step = direction(half_step) * step_length;
+                    if step.magnitude_squared() <= f32::EPSILON {
+                        break;
+                    }
+
+                    position += step;
+                    line.push(position);
+
+                    if !is_inside(position) {
+                        break;
+                    }
+                    if let Some(sink) = charges.iter().find(|sink| sink.charge * sign < 0.0 && sink.position.distance_to(position) < start_radius) {
+                        line.push(sink.position);
+                        break;
+                    }
+                }
+
+                lines.push(line);
+            }
+        }
+
+        lines
+    }
+}
@@ -0,0 +1 @@
+pub mod electrostatics;
\ No newline at end of file
@@ -0,0 +1,125 @@
+use serde::{Serialize, Deserialize};
+
+use crate::core::sciences::maths::Vector2;
+
+/// External force field shared by the simulation templates.
+///
+/// Every field is expressed as an acceleration, so that a template multiplies it by the mass
+/// of its bodies when it works with forces. The fields relaxing the velocity towards a target
+/// (wind, vortex, drag, damping) are integrated explicitly: their rate times the timestep
+/// should stay below 1.
+///
+/// Serialized with an internal `type` tag, e.g.
+/// `{ "type": "attractor", "position": { "x": 400, "y": 300 }, "strength": 500, "radius": 50 }`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ForceField {
+    /// Uniform gravity of `strength` along `direction`, which does not need to be normalized.
+    Gravity { direction: Vector2, strength: f32 },
+    /// Inverse-square attraction towards `position`, of magnitude `strength` at distance `radius`
+    /// and decreasing linearly to zero inside it. A negative strength repels.
+    Attractor { position: Vector2, strength: f32, radius: f32 },
+    /// Rankine vortex around `center`: the velocity relaxes at rate `drag` towards a swirl
+    /// peaking at `speed` at distance `radius`. A positive speed turns clockwise on screen.
+    Vortex { center: Vector2, speed: f32, radius: f32, drag: f32 },
+    /// The velocity relaxes at rate `drag` towards the wind `velocity`.
+    Wind { velocity: Vector2, drag: f32 },
+    /// Drag opposing the velocity, linear and quadratic in the speed.
+    Drag { linear: f32, quadratic: f32 },
+    /// Linear drag of rate `damping` inside the rectangle from `min` to `max`.
+    DampingZone { min: Vector2, max: Vector2, damping: f32 },
+}
+
+impl ForceField {
+    /// Acceleration exerted by the field on a body at `position` moving at `velocity`.
+    pub fn acceleration(&self, position: Vector2, velocity: Vector2) -> Vector2 {
+        match *self {
+            ForceField::Gravity { direction, strength } => match direction.normalize() {
+                Ok(direction) => direction * strength,
+                Err(_) => Vector2::zero()
+            },
+            ForceField::Attractor { position: center, strength, radius } => {
+                let offset = center - position;
+                let distance = offset.magnitude();
+                if distance <= f32::EPSILON {
+                    return Vector2::zero();
+                }
+
+                let radius = radius.max(f32::EPSILON);
+                let magnitude = if distance < radius {
+                    strength * distance / radius
+                } else {
+                    strength * radius * radius / (distance * distance)
+                };
+                offset / distance * magnitude
+            },
+            ForceField::Vortex { center, speed, radius, drag } => {
+                let offset = position - center;
+                let distance = offset.magnitude();
+                if distance <= f32::EPSILON {
+                    return velocity * -drag;
+                }
+
+                let radius = radius.max(f32::EPSILON);
+                let swirl_speed = if distance < radius { speed * distance / radius } else { speed * radius / distance };
+                let swirl = (offset / distance).perpendicular() * swirl_speed;
+                (swirl - velocity) * drag
+            },
+            ForceField::Wind { velocity: wind, drag } => (wind - velocity) * drag,
+            ForceField::Drag { linear, quadratic } => velocity * -(linear + quadratic * velocity.magnitude()),
+            ForceField::DampingZone { min, max, damping } => {
+                let is_inside = position.x >= min.x.min(max.x) && position.x <= min.x.max(max.x)
+                    && position.y >= min.y.min(max.y) && position.y <= min.y.max(max.y);
+                if is_inside { velocity * -damping } else { Vector2::zero() }
+            },
+        }
+    }
+}
+
+/// Sum of the accelerations exerted by `fields` on a body at `position` moving at `velocity`.
+pub fn total_acceleration(fields: &[ForceField], position: Vector2, velocity: Vector2) -> Vector2 {
+    fields.iter().fold(Vector2::zero(), |acceleration, field| acceleration + field.acceleration(position, velocity))
+}
+
+/// Handles the `add_force_field`, `remove_force_field` (by index) and `clear_force_fields` events,
+/// which every template supporting force fields forwards here.
+pub fn handle_force_field_event(fields: &mut Vec<ForceField>, event: &str, data: Option<String>) -> Result<(), String> {
+    match event {
+        "add_force_field" => {
+            match data {
+                Some(data) => {
+                    let field: ForceField = match serde_json::from_str(&data) {
+                        Ok(deserialized_data) => deserialized_data,
+                        Err(e) => return Err(e.to_string())
+                    };
+
+                    fields.push(field);
+                },
+                None => return Err("No data provided for the 'add_force_field' event".to_string())
+            }
+        },
+        "remove_force_field" => {
+            match data {
+                Some(data) => {
+                    let index: usize = match serde_json::from_str(&data) {
+                        Ok(deserialized_data) => deserialized_data,
+                        Err(e) => return Err(e.to_string())
+                    };
+
+                    if index >= fields.len() {
+                        return Err(format!("No force field at index {}", index));
+                    }
+
+                    fields.remove(index);
+                },
+                None => return Err("No data provided for the 'remove_force_field' event".to_string())
+            }
+        },
+        "clear_force_fields" => {
+            fields.clear();
+        },
+        _ => return Err("Unknown event".to_string())
+    }
+
+    Ok(())
+}
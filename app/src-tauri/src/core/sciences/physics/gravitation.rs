@@ -0,0 +1,206 @@
+use serde::Serialize;
+
+use crate::core::sciences::maths::Vector2;
+
+/// Depth below which the bodies are no longer split, so that coincident bodies cannot recurse forever.
+const MAX_DEPTH: usize = 32;
+
+/// Square cell of the quadtree, sent to the renderer to display the tree.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct TreeCell {
+    pub min: Vector2,
+    pub size: f32,
+}
+
+struct QuadNode {
+    cell: TreeCell,
+    mass: f32,
+    /// Center of mass, accumulated as the mass-weighted sum of the positions while the tree is built.
+    center_of_mass: Vector2,
+    /// Index of the first of the four children, stored next to each other. 0 for a leaf, the root being never a child.
+    first_child: usize,
+    /// Bodies of a leaf: a single one, or several when they are closer than the maximum depth allows to split.
+    bodies: Vec<usize>,
+}
+
+impl QuadNode {
+    fn new(min: Vector2, size: f32) -> Self {
+        QuadNode {
+            cell: TreeCell { min, size },
+            mass: 0.0,
+            center_of_mass: Vector2::zero(),
+            first_child: 0,
+            bodies: Vec::new(),
+        }
+    }
+
+    fn contains(&self, position: Vector2) -> bool {
+        let TreeCell { min, size } = self.cell;
+        position.x >= min.x && position.x <= min.x + size && position.y >= min.y && position.y <= min.y + size
+    }
+
+    /// Index of the child, among the four, whose quadrant holds `position`.
+    fn quadrant(&self, position: Vector2) -> usize {
+        let half = self.cell.size / 2.0;
+        let right = position.x >= self.cell.min.x + half;
+        let bottom = position.y >= self.cell.min.y + half;
+        right as usize + 2 * bottom as usize
+    }
+}
+
+/// Barnes–Hut quadtree (Barnes & Hut 1986), approximating the gravitation of the distant groups
+/// of bodies by the one of their center of mass, in O(n log n) instead of O(n²).
+///
+/// A cell of side `s` seen at distance `d` is approximated when `s / d < theta`: 0 gives the exact
+/// sum, 0.5 to 1 the usual compromises. The interactions are softened by a length `softening`,
+/// the potential of a mass `m` at distance `r` being `-m / sqrt(r² + softening²)`, which bounds the
+/// accelerations of the close encounters.
+///
+/// The results are given for a unit gravitational constant.
+pub struct QuadTree {
+    nodes: Vec<QuadNode>,
+}
+
+impl QuadTree {
+    /// Builds the tree of the bodies at `positions`, of masses `masses`.
+    pub fn new(positions: &[Vector2], masses: &[f32]) -> Self {
+        let mut min = Vector2::new(f32::MAX, f32::MAX);
+        let mut max = Vector2::new(f32::MIN, f32::MIN);
+        for position in positions {
+            min = Vector2::new(min.x.min(position.x), min.y.min(position.y));
+            max = Vector2::new(max.x.max(position.x), max.y.max(position.y));
+        }
+        if positions.is_empty() {
+            min = Vector2::zero();
+            max = Vector2::zero();
+        }
+
+        // Slightly larger than the bodies, so that the ones on the border are inside
+        let size = (max.x - min.x).max(max.y - min.y) * 1.001 + 1.0;
+
+        let mut tree = QuadTree { nodes: vec![QuadNode::new(min, size)] };
+        for i in 0..positions.len() {
+            tree.insert(positions, masses, i);
+        }
+
+        for node in &mut tree.nodes {
+            node.center_of_mass = if node.mass > 0.0 {
+                node.center_of_mass / node.mass
+            } else {
+                node.cell.min + Vector2::new(node.cell.size, node.cell.size) / 2.0
+            };
+        }
+
+        tree
+    }
+
+    fn insert(&mut self, positions: &[Vector2], masses: &[f32], i: usize) {
+        let (position, mass) = (positions[i], masses[i]);
+        let mut node = 0;
+        let mut depth = 0;
+
+        loop {
+            self.nodes[node].mass += mass;
+            self.nodes[node].center_of_mass += position * mass;
+
+            if self.nodes[node].first_child != 0 {
+                node = self.nodes[node].first_child + self.nodes[node].quadrant(position);
+                depth += 1;
+                continue;
+            }
+
+            if self.nodes[node].bodies.is_empty() || depth >= MAX_DEPTH {
+                self.nodes[node].bodies.push(i);
+                return;
+            }
+
+            // The leaf is occupied: split it and move its body down before going on
+            self.subdivide(node);
+            for j in std::mem::take(&mut self.nodes[node].bodies) {
+                let child = self.nodes[node].first_child + self.nodes[node].quadrant(positions[j]);
+                self.nodes[child].mass += masses[j];
+                self.nodes[child].center_of_mass += positions[j] * masses[j];
+                self.nodes[child].bodies.push(j);
+            }
+
+            node = self.nodes[node].first_child + self.nodes[node].quadrant(position);
+            depth += 1;
+        }
+    }
+
+    fn subdivide(&mut self, node: usize) {
+        let TreeCell { min, size } = self.nodes[node].cell;
+        let half = size / 2.0;
+
+        self.nodes[node].first_child = self.nodes.len();
+        for quadrant in 0..4 {
+            let offset = Vector2::new((quadrant % 2) as f32 * half, (quadrant / 2) as f32 * half);
+            self.nodes.push(QuadNode::new(min + offset, half));
+        }
+    }
+
+    /// Calls `interact(offset, mass)` for every mass acting on the body `index`, `offset` going from the body
+    /// to the mass: the other bodies of the close leaves, and the centers of mass of the distant cells.
+    fn for_each_interaction<F>(&self, positions: &[Vector2], masses: &[f32], index: usize, theta: f32, mut interact: F)
+    where F: FnMut(Vector2, f32) {
+        let position = positions[index];
+        let mut stack = vec![0];
+
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if node.mass <= 0.0 {
+                continue;
+            }
+
+            if node.first_child == 0 {
+                for &j in node.bodies.iter().filter(|j| **j != index) {
+                    interact(positions[j] - position, masses[j]);
+                }
+                continue;
+            }
+
+            // A cell holding the body is always opened, so that the body never attracts itself
+            let offset = node.center_of_mass - position;
+            if !node.contains(position) && node.cell.size * node.cell.size < theta * theta * offset.magnitude_squared() {
+                interact(offset, node.mass);
+            } else {
+                stack.extend(node.first_child..node.first_child + 4);
+            }
+        }
+    }
+
+    /// Softened gravitational acceleration of the body `index`.
+    pub fn acceleration(&self, positions: &[Vector2], masses: &[f32], index: usize, theta: f32, softening: f32) -> Vector2 {
+        let softening_squared = softening * softening;
+        let mut acceleration = Vector2::zero();
+
+        self.for_each_interaction(positions, masses, index, theta, |offset, mass| {
+            let distance_squared = offset.magnitude_squared() + softening_squared;
+            if distance_squared > 0.0 {
+                acceleration += offset * (mass / (distance_squared * distance_squared.sqrt()));
+            }
+        });
+
+        acceleration
+    }
+
+    /// Softened gravitational potential at the body `index`, created by all the other bodies.
+    pub fn potential(&self, positions: &[Vector2], masses: &[f32], index: usize, theta: f32, softening: f32) -> f32 {
+        let softening_squared = softening * softening;
+        let mut potential = 0.0;
+
+        self.for_each_interaction(positions, masses, index, theta, |offset, mass| {
+            let distance_squared = offset.magnitude_squared() + softening_squared;
+            if distance_squared > 0.0 {
+                potential -= mass / distance_squared.sqrt();
+            }
+        });
+
+        potential
+    }
+
+    /// Cells of the tree, leaves included.
+    pub fn cells(&self) -> Vec<TreeCell> {
+        self.nodes.iter().filter(|node| node.mass > 0.0).map(|node| node.cell).collect()
+    }
+}
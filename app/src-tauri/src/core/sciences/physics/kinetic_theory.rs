@@ -0,0 +1,77 @@
+use std::f32::consts::PI;
+
+use rand::Rng;
+use serde::Serialize;
+
+/// Temperature of a two-dimensional ideal gas of `particle_count` particles of total kinetic energy `kinetic_energy`.
+///
+/// Each particle has two degrees of freedom, each holding `k T / 2` on average (equipartition).
+pub fn temperature_2d(kinetic_energy: f32, particle_count: usize, boltzmann_constant: f32) -> f32 {
+    if particle_count == 0 || boltzmann_constant <= 0.0 {
+        return 0.0;
+    }
+
+    kinetic_energy / (particle_count as f32 * boltzmann_constant)
+}
+
+/// Most probable speed of the two-dimensional Maxwell–Boltzmann distribution, `sqrt(k T / m)`.
+pub fn most_probable_speed_2d(mass: f32, temperature: f32, boltzmann_constant: f32) -> f32 {
+    (boltzmann_constant * temperature.max(0.0) / mass.max(f32::EPSILON)).sqrt()
+}
+
+/// Probability density of the speed `speed` in a two-dimensional ideal gas at `temperature`,
+/// `f(v) = m v / (k T) exp(-m v² / (2 k T))` (Maxwell–Boltzmann).
+pub fn maxwell_boltzmann_2d(speed: f32, mass: f32, temperature: f32, boltzmann_constant: f32) -> f32 {
+    let thermal_energy = boltzmann_constant * temperature;
+    if thermal_energy <= 0.0 || speed < 0.0 {
+        return 0.0;
+    }
+
+    mass * speed / thermal_energy * (-mass * speed * speed / (2.0 * thermal_energy)).exp()
+}
+
+/// Sample of the two-dimensional Maxwell–Boltzmann distribution, a Rayleigh law, returned as a velocity
+/// `(vx, vy)` in a random direction.
+pub fn sample_velocity_2d(rng: &mut impl Rng, mass: f32, temperature: f32, boltzmann_constant: f32) -> (f32, f32) {
+    let speed = most_probable_speed_2d(mass, temperature, boltzmann_constant) * (-2.0 * rng.gen_range(f32::EPSILON..1.0).ln()).sqrt();
+    let angle = rng.gen_range(0.0..2.0 * PI);
+    (speed * angle.cos(), speed * angle.sin())
+}
+
+/// Histogram of the speeds of the particles, normalized as a probability density to be compared with
+/// the Maxwell–Boltzmann distribution sampled at the bin centers.
+#[derive(Serialize, Clone, Default)]
+pub struct SpeedHistogram {
+    pub bin_width: f32,
+    pub densities: Vec<f32>,
+    pub maxwell_boltzmann: Vec<f32>,
+}
+
+impl SpeedHistogram {
+    /// Sorts `speeds` in `bin_count` bins between 0 and `max_speed`, the faster particles being
+    /// counted in the total but in no bin.
+    pub fn new(speeds: &[f32], bin_count: usize, max_speed: f32, mass: f32, temperature: f32, boltzmann_constant: f32) -> Self {
+        if bin_count == 0 || max_speed <= 0.0 {
+            return SpeedHistogram::default();
+        }
+
+        let bin_width = max_speed / bin_count as f32;
+        let mut densities = vec![0.0; bin_count];
+        for speed in speeds {
+            let bin = (speed / bin_width) as usize;
+            if bin < bin_count {
+                densities[bin] += 1.0;
+            }
+        }
+        if !speeds.is_empty() {
+            let normalization = 1.0 / (speeds.len() as f32 * bin_width);
+            densities.iter_mut().for_each(|density| *density *= normalization);
+        }
+
+        let maxwell_boltzmann = (0..bin_count)
+            .map(|bin| maxwell_boltzmann_2d((bin as f32 + 0.5) * bin_width, mass, temperature, boltzmann_constant))
+            .collect();
+
+        SpeedHistogram { bin_width, densities, maxwell_boltzmann }
+    }
+}
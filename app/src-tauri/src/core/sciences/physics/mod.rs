@@ -1 +1,5 @@
-pub mod rigid_body;
\ No newline at end of file
+pub mod rigid_body;
+pub mod force_fields;
+pub mod gravitation;
+pub mod electromagnetism;
+pub mod kinetic_theory;
\ No newline at end of file
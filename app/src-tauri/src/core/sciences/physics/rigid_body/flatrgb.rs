@@ -1,11 +1,12 @@
 use super::r#struct::{ShapeType, Vector2D,WorkSpace,FlatTransform,AABB};
 use super::r#struct::RigidBody;
-use  super::physics::G;
 use super::flataabb::init_hard_aabb;
 use std::f64::consts::PI;
 use super::flattransfom::init_tf_zero;
 use super::r#struct::ShapeType::Circle;
 use super::vectormath::{dot_s, sm, c_vect, vec_zero, transform_v};
+use crate::core::sciences::maths::Vector2;
+use crate::core::sciences::maths::integrators::Integrator;
 
 
 impl RigidBody{
@@ -37,13 +38,24 @@ impl RigidBody{
         self.tfv_required = false;
         self.transformed_vertices.clone()
     }
-    pub fn step_body(&mut self,mut time:f64,iterations:i32){
+    /// Moves the body over `time / iterations` with `integrator`, under `acceleration(position, velocity)`
+    /// (the gravity and the force fields) and its accumulated `force`.
+    pub fn step_body<F>(&mut self,mut time:f64,iterations:i32,integrator:Integrator,acceleration:F)
+    where F: Fn(Vector2, Vector2) -> Vector2 {
         if self.is_static { return; }
-        //let acc = div_s(self.force,self.mass);
-        //self.linear_velocity = sm(self.linear_velocity,dot_s(acc,time));
         time = time / iterations as f64;
-        self.linear_velocity = sm(self.linear_velocity,dot_s(G,time));
-        self.position = sm(self.position,dot_s(self.linear_velocity,time));
+        if self.mass > 0.0 {
+            let force_acceleration = Vector2::new((self.force.x / self.mass) as f32, (self.force.y / self.mass) as f32);
+            let position = Vector2::new(self.position.x as f32, self.position.y as f32);
+            let velocity = Vector2::new(self.linear_velocity.x as f32, self.linear_velocity.y as f32);
+            let (position, velocity) = integrator.step(position, velocity, time as f32, |position, velocity| {
+                acceleration(position, velocity) + force_acceleration
+            });
+            self.position = c_vect(position.x as f64, position.y as f64);
+            self.linear_velocity = c_vect(velocity.x as f64, velocity.y as f64);
+        } else {
+            self.position = sm(self.position,dot_s(self.linear_velocity,time));
+        }
         self.angle += self.angular_velocity * time;
 
         self.force=vec_zero();
@@ -7,6 +7,8 @@ use serde::{Serialize,Deserialize};
 use std::f64::consts::PI;
 use super::flatrgb::{triangulate_box, which_shape};
 use super::vectormath::{c_vect, vec_zero};
+use crate::core::sciences::physics::force_fields::ForceField;
+use crate::core::sciences::maths::integrators::Integrator;
 
 #[derive(Serialize,Deserialize,  Debug, Clone, Copy)]
 pub struct Vector2D {
@@ -272,6 +274,9 @@ pub struct WorkSpace{
     pub max_iter:i32,
     pub body_list:Vec<RigidBody>,
     pub gravity: Vector2D,
+    pub force_fields: Vec<ForceField>,
+    /// Scheme moving the bodies between the contacts.
+    pub integrator: Integrator,
     pub body_count : usize,
     pub contact_pair : Vec<(usize,usize)>
 }
@@ -326,6 +331,8 @@ impl  WorkSpace {
             max_iter: 128,
             body_list: Vec::new(),
             gravity: c_vect(0.0, 9.81),
+            force_fields: Vec::new(),
+            integrator: Integrator::default(),
             body_count: 0,
             contact_pair:Vec::new(),
         }
@@ -4,6 +4,8 @@ use super::collisions::{intersect_aabbs,collide, find_contact_points, min};
 use super::flatmanifold::init_manifold;
 use super::flatrgb::clamp_for_int;
 use super::vectormath::{dot_s, sm, mn, c_vect, vec_zero};
+use crate::core::sciences::maths::Vector2;
+use crate::core::sciences::physics::force_fields::total_acceleration;
 
 //static mut BODIES : Option<Vec<RigidBody>> = None;
 
@@ -74,9 +76,12 @@ impl WorkSpace{
     }
     #[allow(dead_code)]
     pub fn step_bodies(&mut self,time:f64,iterations:i32){
-        for i in 0..self.body_list.len() {
-            self.body_list[i].force=self.gravity.clone();
-            self.body_list[i].step_body(time,iterations);
+        let gravity = Vector2::new(self.gravity.x as f32, self.gravity.y as f32);
+        let force_fields = &self.force_fields;
+        for body in &mut self.body_list {
+            body.step_body(time, iterations, self.integrator, |position, velocity| {
+                gravity + total_acceleration(force_fields, position, velocity)
+            });
         }
     }
     #[allow(dead_code)]
@@ -0,0 +1,10 @@
+import type { Vector2 } from "./vector2";
+
+// External force field, expressed as an acceleration, shared by the simulation templates.
+export type ForceField =
+    | { type: "gravity", direction: Vector2, strength: number }
+    | { type: "attractor", position: Vector2, strength: number, radius: number }
+    | { type: "vortex", center: Vector2, speed: number, radius: number, drag: number }
+    | { type: "wind", velocity: Vector2, drag: number }
+    | { type: "drag", linear: number, quadratic: number }
+    | { type: "damping_zone", min: Vector2, max: Vector2, damping: number };
@@ -0,0 +1,10 @@
+// Numerical scheme moving the bodies of a simulation template, see `sciences::maths::integrators`.
+export type Integrator = "explicit_euler" | "semi_implicit_euler" | "velocity_verlet" | "rk4" | "rk45";
+
+export const integrator_names: Record<Integrator, string> = {
+    explicit_euler: "Explicit Euler",
+    semi_implicit_euler: "Semi-Implicit Euler",
+    velocity_verlet: "Velocity Verlet",
+    rk4: "RK4",
+    rk45: "Adaptive RK45",
+};
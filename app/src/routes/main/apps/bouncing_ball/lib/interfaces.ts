@@ -1,13 +1,37 @@
 import type { Vector2 } from "$lib/components/app/Interfaces/vector2";
+import type { ForceField } from "$lib/components/app/Interfaces/force_field";
+import type { Integrator } from "$lib/components/app/Interfaces/integrator";
 
 export interface Ball {
     position: Vector2,
     velocity: Vector2,
     radius: number,
     mass: number,
+    restitution: number,
     color: string,
+    custom_color: boolean,
 }
 
 export interface RendererData {
     balls: Ball[],
+    force_fields: ForceField[],
+    momentum: Vector2,
+    kinetic_energy: number,
+    potential_energy: number,
+}
+
+export interface EventSettings {
+    gravity: number,
+    integrator: Integrator,
+    radius: number,
+    mass: number,
+    restitution: number,
+}
+
+export interface EventEditBall {
+    index: number,
+    radius: number,
+    mass: number,
+    restitution: number,
+    color: string | null,
 }
\ No newline at end of file
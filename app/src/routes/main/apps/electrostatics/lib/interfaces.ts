@@ -0,0 +1,52 @@
+import type { Vector2 } from "$lib/components/app/Interfaces/vector2";
+import type { ForceField } from "$lib/components/app/Interfaces/force_field";
+
+export interface Charge {
+    position: Vector2,
+    velocity: Vector2,
+    charge: number,
+    mass: number,
+    fixed: boolean,
+}
+
+export interface FieldGrid {
+    width: number,
+    height: number,
+    cell_size: number,
+    potentials: number[],
+    fields: Vector2[],
+}
+
+export interface EventAddCharge {
+    position: Vector2,
+    charge: number,
+    fixed?: boolean,
+    velocity?: Vector2,
+    mass?: number,
+}
+
+export interface ElectrostaticsStarterData {
+    charges: EventAddCharge[],
+}
+
+export interface RendererData {
+    charges: Charge[],
+    charge_radius: number,
+    field_grid: FieldGrid,
+    pixels: number[],
+    field_lines: Vector2[][],
+    force_fields: ForceField[],
+}
+
+export interface EventSettings {
+    coulomb_constant: number,
+    softening: number,
+    external_field: Vector2,
+    damping: number,
+    restitution: number,
+    friction: number,
+    show_potential: boolean,
+    show_field_lines: boolean,
+    grid_cell_size: number,
+    lines_per_charge: number,
+}
@@ -0,0 +1,27 @@
+import type { Vector2 } from "$lib/components/app/Interfaces/vector2";
+import type { ForceField } from "$lib/components/app/Interfaces/force_field";
+import type { FluidParticles, ColorRange } from "../../fluid/lib/interfaces";
+
+export interface FlipStarterData {
+    positions: Vector2[],
+}
+
+export type VisualFilter = "none" | "speed" | "density";
+
+export interface RendererData {
+    fluid_particles: FluidParticles,
+    displayed_range: [number, number],
+    force_fields: ForceField[],
+}
+
+export interface EventSettings {
+    gravity: number,
+    flip_ratio: number,
+    pressure_iterations: number,
+    over_relaxation: number,
+    separation_iterations: number,
+    compensate_drift: boolean,
+    visual_filter: VisualFilter,
+    color_range: ColorRange,
+    interactive_force_mode: boolean
+}
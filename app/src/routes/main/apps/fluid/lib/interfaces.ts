@@ -1,4 +1,6 @@
 import type { Vector2 } from "$lib/components/app/Interfaces/vector2";
+import type { ForceField } from "$lib/components/app/Interfaces/force_field";
+import type { Integrator } from "$lib/components/app/Interfaces/integrator";
 
 /*
 pub struct FluidParticles {
@@ -16,8 +18,55 @@ pub struct FluidParticles {
 }
 */
 
+export type ObstacleShape =
+    | { type: "circle", center: Vector2, radius: number }
+    | { type: "rectangle", center: Vector2, size: Vector2, rotation: number }
+    | { type: "polygon", vertices: Vector2[] };
+
+export interface Obstacle {
+    shape: ObstacleShape,
+    restitution: number,
+    friction: number,
+}
+
+export interface Emitter {
+    position: Vector2,
+    direction: Vector2,
+    rate: number,
+    speed: number,
+    phase: number,
+    width?: number,
+}
+
+export interface Sink {
+    shape: ObstacleShape,
+}
+
+export interface ThermalRegion {
+    shape: ObstacleShape,
+    temperature: number,
+    transfer_rate: number,
+}
+
+export type FluidLayout =
+    | { type: "block", min: Vector2, max: Vector2, spacing: number, jitter?: number }
+    | { type: "dam_break", width: number, height: number, spacing: number }
+    | { type: "droplet", center: Vector2, radius: number, spacing: number }
+    | { type: "random_fill", min: Vector2, max: Vector2, count: number }
+    | { type: "poisson_disk", vertices: Vector2[], min_distance: number };
+
 export interface FluidStarterData {
-    positions: Vector2[],
+    positions?: Vector2[],
+    layouts?: FluidLayout[],
+    obstacles?: Obstacle[],
+    emitters?: Emitter[],
+    sinks?: Sink[],
+    thermal_regions?: ThermalRegion[],
+}
+
+export interface PeriodicBoundaries {
+    x: boolean,
+    y: boolean,
 }
 
 export interface FluidParticles {
@@ -26,20 +75,52 @@ export interface FluidParticles {
     target_density: number,
     pressure_multiplier: number,
     smoothing_radius: number,
+    ambient_temperature: number,
+    periodic: PeriodicBoundaries,
+    domain_size: Vector2,
     positions: Vector2[],
     velocities: Vector2[],
     densities: number[],
     colors: string[],
+    phases: number[],
+    temperatures: number[],
+    pressures: number[],
+}
+
+export interface SolverMetrics {
+    iterations: number,
+    density_error: number,
+}
+
+export interface SurfaceContour {
+    points: Vector2[],
+    hole: boolean,
+}
+
+export interface FluidSurface {
+    contours: SurfaceContour[],
+    free_surface_length: number,
+    area: number,
 }
 
 export interface RendererData {
     fluid_particles: FluidParticles,
+    obstacles: Obstacle[],
+    emitters: Emitter[],
+    sinks: Sink[],
+    thermal_regions: ThermalRegion[],
+    metrics: SolverMetrics,
+    substeps: number,
+    displayed_range: [number, number],
+    surface: FluidSurface | null,
+    force_fields: ForceField[],
 }
 
 /*
 pub struct EventSettings {
     pub collision_restitution: f32,
     pub gravity: f32,
+    pub integrator: Integrator,
     pub target_density: f32,
     pub mass: f32,
     pub pressure_stiffness: f32,
@@ -48,14 +129,50 @@ pub struct EventSettings {
 }
 */
 
+export type FluidSolver = "explicit_sph" | "position_based" | "implicit";
+
+export type Kernel = "poly6" | "spiky" | "spiky_quadratic" | "cubic_spline" | "wendland_c2" | "viscosity";
+
+export interface KernelSettings {
+    density: Kernel,
+    pressure: Kernel,
+    viscosity: Kernel,
+}
+
+export type VisualFilter = "none" | "speed" | "pressure" | "density" | "vorticity" | "neighbour_count" | "phase" | "temperature";
+
+export type ColorRange =
+    | { mode: "auto" }
+    | { mode: "fixed", min: number, max: number };
+
 export interface EventSettings {
     collision_restitution: number,
     gravity: number,
+    integrator: Integrator,
     target_density: number,
     mass: number,
     pressure_stiffness: number,
-    visual_filter: number,
+    visual_filter: VisualFilter,
+    color_range: ColorRange,
+    show_surface: boolean,
+    surface_resolution: number,
+    surface_iso_level: number,
     smoothing_radius: number,
     viscosity_strength: number,
-    interactive_force_mode: boolean
+    interactive_force_mode: boolean,
+    surface_tension_strength: number,
+    adhesion_strength: number,
+    use_boundary_particles: boolean,
+    periodic: PeriodicBoundaries,
+    solver: FluidSolver,
+    solver_iterations: number,
+    kernels: KernelSettings,
+    max_density_error: number,
+    thermal_diffusivity: number,
+    thermal_expansion: number,
+    viscoelastic: boolean,
+    spring_stiffness: number,
+    yield_ratio: number,
+    plasticity: number,
+    ambient_temperature: number
 }
\ No newline at end of file
@@ -0,0 +1,32 @@
+import type { Vector2 } from "$lib/components/app/Interfaces/vector2";
+import type { ForceField } from "$lib/components/app/Interfaces/force_field";
+import type { FluidParticles, FluidStarterData, FluidSurface } from "../../fluid/lib/interfaces";
+import type { LightRigidBody } from "../../rigidbody/lib/interfaces";
+
+export type BodyShape = "Circle" | "Box";
+
+// Rigid body to add to the scene, `position` being its center.
+export interface BodyDescription {
+    shape: BodyShape,
+    position: Vector2,
+    rotation?: number,
+    radius?: number,
+    width?: number,
+    height?: number,
+    density: number,
+    restitution: number,
+    is_static?: boolean,
+}
+
+export interface FluidRigidStarterData extends FluidStarterData {
+    bodies?: BodyDescription[],
+}
+
+// Bodies are sent with their center as position, unlike the rigid body template.
+export interface RendererData {
+    fluid_particles: FluidParticles,
+    bodies: LightRigidBody[],
+    substeps: number,
+    surface: FluidSurface | null,
+    force_fields: ForceField[],
+}
@@ -0,0 +1,42 @@
+import type { Vector2 } from "$lib/components/app/Interfaces/vector2";
+import type { ForceField } from "$lib/components/app/Interfaces/force_field";
+
+export interface Particles {
+    positions: Vector2[],
+    velocities: Vector2[],
+    colors: string[],
+}
+
+export interface SpeedHistogram {
+    bin_width: number,
+    densities: number[],
+    maxwell_boltzmann: number[],
+}
+
+export interface IdealGasStarterData {
+    particle_count: number,
+    temperature: number,
+    particle_radius: number,
+    particle_mass: number,
+}
+
+export interface RendererData {
+    particles: Particles,
+    particle_radius: number,
+    piston_position: number,
+    area: number,
+    kinetic_energy: number,
+    temperature: number,
+    pressure: number,
+    piston_pressure: number,
+    compressibility_factor: number,
+    histogram: SpeedHistogram,
+    substeps: number,
+    force_fields: ForceField[],
+}
+
+export interface EventSettings {
+    piston_max_speed: number,
+    measurement_window: number,
+    histogram_bins: number,
+}
@@ -0,0 +1,42 @@
+import type { Vector2 } from "$lib/components/app/Interfaces/vector2";
+import type { ForceField } from "$lib/components/app/Interfaces/force_field";
+
+export interface Bodies {
+    positions: Vector2[],
+    velocities: Vector2[],
+    masses: number[],
+    radii: number[],
+    colors: string[],
+}
+
+export interface TreeCell {
+    min: Vector2,
+    size: number,
+}
+
+// Initial conditions of a group of bodies, generated by the backend.
+export type Generator =
+    | { type: "body", position: Vector2, velocity: Vector2, mass: number }
+    | { type: "orbit", center: Vector2, velocity: Vector2, central_mass: number, body_count: number, body_mass: number, inner_radius: number, outer_radius: number }
+    | { type: "disc_galaxy", center: Vector2, velocity: Vector2, core_mass: number, body_count: number, disc_mass: number, radius: number }
+    | { type: "plummer_sphere", center: Vector2, velocity: Vector2, body_count: number, total_mass: number, scale_radius: number };
+
+export interface NBodyStarterData {
+    generators: Generator[],
+}
+
+export interface RendererData {
+    bodies: Bodies,
+    tree_cells: TreeCell[],
+    kinetic_energy: number,
+    potential_energy: number,
+    substeps: number,
+    force_fields: ForceField[],
+}
+
+export interface EventSettings {
+    gravitational_constant: number,
+    softening: number,
+    theta: number,
+    show_tree: boolean,
+}
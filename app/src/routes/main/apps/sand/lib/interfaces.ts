@@ -0,0 +1,40 @@
+import type { Vector2 } from "$lib/components/app/Interfaces/vector2";
+import type { ForceField } from "$lib/components/app/Interfaces/force_field";
+
+export interface Grains {
+    positions: Vector2[],
+    velocities: Vector2[],
+    angles: number[],
+    angular_velocities: number[],
+    radii: number[],
+    colors: string[],
+}
+
+export interface Segment {
+    start: Vector2,
+    end: Vector2,
+}
+
+export interface SandStarterData {
+    positions: Vector2[],
+    segments: Segment[],
+}
+
+export interface RendererData {
+    grains: Grains,
+    segments: Segment[],
+    substeps: number,
+    repose_angle: number | null,
+    force_fields: ForceField[],
+}
+
+export interface EventSettings {
+    gravity: number,
+    grain_radius: number,
+    polydispersity: number,
+    normal_stiffness: number,
+    restitution: number,
+    friction: number,
+    rolling_friction: number,
+    pour_rate: number,
+}
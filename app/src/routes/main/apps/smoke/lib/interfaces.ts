@@ -0,0 +1,30 @@
+import type { Vector2 } from "$lib/components/app/Interfaces/vector2";
+import type { ForceField } from "$lib/components/app/Interfaces/force_field";
+
+export interface SmokeStarterData {
+    cell_size: number,
+}
+
+export interface RendererData {
+    width: number,
+    height: number,
+    cell_size: number,
+    pixels: number[],
+    force_fields: ForceField[],
+}
+
+export interface EventSettings {
+    viscosity: number,
+    diffusion: number,
+    dissipation: number,
+    vorticity_strength: number,
+    buoyancy: number,
+    solver_iterations: number,
+    injection_radius: number,
+    injection_amount: number
+}
+
+export interface EventInjection {
+    position: Vector2,
+    velocity: Vector2
+}